@@ -0,0 +1,534 @@
+use crate::music::Music;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
+
+pub const SAMPLE_RATE: u32 = 44_100;
+
+// Waveforms of the voices, as plain as those of the sound chips of the time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wave {
+    Square,
+    Triangle,
+    Noise,
+}
+
+// A single note, sweeping from one frequency to another while fading out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tone {
+    pub wave: Wave,
+    pub from: f32,
+    pub to: f32,
+    pub millis: u64,
+    pub volume: f32,
+}
+
+impl Tone {
+    pub fn new(wave: Wave, from: f32, to: f32, millis: u64, volume: f32) -> Self {
+        Self {
+            wave,
+            from,
+            to,
+            millis,
+            volume,
+        }
+    }
+
+    // Appends the samples of the tone, in the -1.0..=1.0 range.
+    pub fn render(&self, sample_rate: u32, samples: &mut Vec<f32>) {
+        let len = (sample_rate as u64 * self.millis / 1000) as usize;
+        let mut phase = 0.0f32;
+        // 15 bits linear feedback shift register, for the noise
+        let mut register = 1u16;
+
+        for i in 0..len {
+            let progress = i as f32 / len as f32;
+            let frequency = self.from + (self.to - self.from) * progress;
+            let value = match self.wave {
+                Wave::Square => {
+                    if phase < 0.5 {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                Wave::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+                Wave::Noise => {
+                    let bit = (register ^ (register >> 1)) & 1;
+                    register = (register >> 1) | (bit << 14);
+                    if register & 1 == 1 {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+            };
+            samples.push(value * self.volume * (1.0 - progress));
+            phase = (phase + frequency / sample_rate as f32).fract();
+        }
+    }
+}
+
+// Frequency of the note `semitones` away from the A above middle C.
+pub fn note(semitones: i32) -> f32 {
+    440.0 * 2f32.powf(semitones as f32 / 12.0)
+}
+
+// Everything in the game that makes a sound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sfx {
+    Move,
+    Rotate,
+    Hold,
+    Land,
+    Match,
+    Chain(usize),
+    LevelUp,
+    GameOver,
+}
+
+impl Sfx {
+    pub fn tones(&self) -> Vec<Tone> {
+        use Wave::*;
+
+        match self {
+            Sfx::Move => vec![Tone::new(Square, note(-12), note(-12), 25, 0.10)],
+            Sfx::Rotate => vec![Tone::new(Square, note(0), note(7), 40, 0.10)],
+            // a column set aside slides away, down and out
+            Sfx::Hold => vec![Tone::new(Triangle, note(7), note(-5), 60, 0.20)],
+            Sfx::Land => vec![
+                Tone::new(Noise, 0.0, 0.0, 30, 0.15),
+                Tone::new(Triangle, note(-24), note(-36), 70, 0.30),
+            ],
+            Sfx::Match => vec![Tone::new(Square, note(3), note(15), 120, 0.15)],
+            // a major arpeggio, a whole tone higher with every step of the chain
+            Sfx::Chain(step) => {
+                let root = 3 + 2 * (*step).min(12) as i32;
+                [0, 4, 7, 12]
+                    .iter()
+                    .map(|interval| {
+                        Tone::new(
+                            Square,
+                            note(root + interval),
+                            note(root + interval),
+                            50,
+                            0.15,
+                        )
+                    })
+                    .collect()
+            }
+            Sfx::LevelUp => [3, 7, 10, 15, 19, 22, 27]
+                .iter()
+                .map(|semitones| Tone::new(Square, note(*semitones), note(*semitones), 60, 0.15))
+                .collect(),
+            Sfx::GameOver => [-2, -5, -9, -14]
+                .iter()
+                .map(|semitones| {
+                    Tone::new(Triangle, note(*semitones), note(semitones - 1), 250, 0.35)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn synthesize(&self, sample_rate: u32) -> Vec<f32> {
+        let mut samples = Vec::new();
+        for tone in self.tones() {
+            tone.render(sample_rate, &mut samples);
+        }
+        samples
+    }
+}
+
+// Wherever the sound ends up: speakers, a file or nowhere at all.
+pub trait AudioSink {
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    // Mixes the samples in with whatever is already playing.
+    fn play(&mut self, samples: &[f32]);
+
+    // Queues the samples right after the ones streamed before, for the music.
+    fn stream(&mut self, samples: &[f32]) {
+        self.play(samples);
+    }
+
+    // Lets time go by, for the sinks that do not keep their own.
+    fn advance(&mut self, _delta: Duration) {}
+
+    // Flushes what was played so far, e.g. to disk.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Silence, for when there is no sound hardware or no wish to hear it.
+#[derive(Debug, Default)]
+pub struct NullSink;
+
+impl AudioSink for NullSink {
+    fn play(&mut self, _samples: &[f32]) {}
+}
+
+// Records everything played along a timeline, and writes it as a WAV file.
+#[derive(Debug)]
+pub struct WavSink {
+    path: PathBuf,
+    samples: Vec<f32>,
+    cursor: usize,
+    stream_cursor: usize,
+}
+
+impl WavSink {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            samples: Vec::new(),
+            cursor: 0,
+            stream_cursor: 0,
+        }
+    }
+
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+}
+
+impl AudioSink for WavSink {
+    fn play(&mut self, samples: &[f32]) {
+        mix(&mut self.samples, self.cursor, samples);
+    }
+
+    fn stream(&mut self, samples: &[f32]) {
+        // never behind the effects, nobody can go back in time to hear it
+        self.stream_cursor = self.stream_cursor.max(self.cursor);
+        mix(&mut self.samples, self.stream_cursor, samples);
+        self.stream_cursor += samples.len();
+    }
+
+    fn advance(&mut self, delta: Duration) {
+        self.cursor += (delta.as_secs_f64() * self.sample_rate() as f64) as usize;
+        if self.samples.len() < self.cursor {
+            self.samples.resize(self.cursor, 0.0);
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(&self.path)?);
+        write_wav(&mut file, self.sample_rate(), &self.samples)?;
+        file.flush()
+    }
+}
+
+// Adds the samples into the buffer from `at` onwards, growing it as needed.
+pub fn mix(buffer: &mut Vec<f32>, at: usize, samples: &[f32]) {
+    if buffer.len() < at + samples.len() {
+        buffer.resize(at + samples.len(), 0.0);
+    }
+    for (mixed, sample) in buffer[at..].iter_mut().zip(samples) {
+        *mixed += sample;
+    }
+}
+
+// Writes the samples as a mono, 16 bits PCM WAV file.
+pub fn write_wav(writer: &mut impl Write, sample_rate: u32, samples: &[f32]) -> io::Result<()> {
+    let data_len = (samples.len() * 2) as u32;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_len).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    // PCM, mono
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * 2).to_le_bytes())?;
+    // block align and bits per sample
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_len.to_le_bytes())?;
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        writer.write_all(&value.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "device")]
+mod device {
+    use super::AudioSink;
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    // The default output device of the system, through cpal.
+    pub struct DeviceSink {
+        // the sound stops as soon as the stream is dropped
+        _stream: cpal::Stream,
+        sample_rate: u32,
+        queues: Arc<Mutex<Queues>>,
+    }
+
+    #[derive(Default)]
+    struct Queues {
+        effects: VecDeque<f32>,
+        music: VecDeque<f32>,
+    }
+
+    impl DeviceSink {
+        pub fn open() -> Option<Self> {
+            let device = cpal::default_host().default_output_device()?;
+            let config = device.default_output_config().ok()?;
+            let sample_rate = config.sample_rate().0;
+            let channels = config.channels() as usize;
+            let queues = Arc::new(Mutex::new(Queues::default()));
+            let shared = Arc::clone(&queues);
+
+            let stream = device
+                .build_output_stream(
+                    &config.into(),
+                    move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                        let mut queues = shared.lock().unwrap();
+                        for frame in data.chunks_mut(channels) {
+                            let sample = queues.effects.pop_front().unwrap_or_default()
+                                + queues.music.pop_front().unwrap_or_default();
+                            frame.fill(sample.clamp(-1.0, 1.0));
+                        }
+                    },
+                    // glitches are not worth stopping the game for
+                    |_| {},
+                    None,
+                )
+                .ok()?;
+            stream.play().ok()?;
+
+            Some(Self {
+                _stream: stream,
+                sample_rate,
+                queues,
+            })
+        }
+    }
+
+    impl AudioSink for DeviceSink {
+        fn sample_rate(&self) -> u32 {
+            self.sample_rate
+        }
+
+        fn play(&mut self, samples: &[f32]) {
+            let effects = &mut self.queues.lock().unwrap().effects;
+            for (i, sample) in samples.iter().enumerate() {
+                match effects.get_mut(i) {
+                    Some(mixed) => *mixed += sample,
+                    None => effects.push_back(*sample),
+                }
+            }
+        }
+
+        fn stream(&mut self, samples: &[f32]) {
+            let music = &mut self.queues.lock().unwrap().music;
+            music.extend(samples);
+            // catch up rather than lag further and further behind the game
+            let latency = (self.sample_rate / 4) as usize;
+            if music.len() > latency {
+                music.drain(..music.len() - latency);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "device")]
+pub use device::DeviceSink;
+
+// Where to send the sound, as picked on the command line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum AudioOutput {
+    #[default]
+    Device,
+    Off,
+    Wav(PathBuf),
+}
+
+impl AudioOutput {
+    // Falls back to silence when there is no device to play on.
+    pub fn open(&self) -> Box<dyn AudioSink> {
+        match self {
+            #[cfg(feature = "device")]
+            AudioOutput::Device => match DeviceSink::open() {
+                Some(sink) => Box::new(sink),
+                None => Box::new(NullSink),
+            },
+            #[cfg(not(feature = "device"))]
+            AudioOutput::Device => Box::new(NullSink),
+            AudioOutput::Off => Box::new(NullSink),
+            AudioOutput::Wav(path) => Box::new(WavSink::new(path.clone())),
+        }
+    }
+}
+
+impl FromStr for AudioOutput {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "device" => Ok(AudioOutput::Device),
+            "off" => Ok(AudioOutput::Off),
+            path if path.ends_with(".wav") => Ok(AudioOutput::Wav(PathBuf::from(path))),
+            _ => Err(()),
+        }
+    }
+}
+
+// Plays the sound effects on a sink, synthesizing each of them only once,
+// along with the music.
+pub struct Audio {
+    sink: Box<dyn AudioSink>,
+    effects: HashMap<Sfx, Vec<f32>>,
+    music: Music,
+    music_playing: bool,
+    // fraction of a sample of music left over from the last advance
+    music_lag: f64,
+}
+
+impl Audio {
+    pub fn new(sink: Box<dyn AudioSink>) -> Self {
+        Self {
+            sink,
+            effects: HashMap::new(),
+            music: Music::default(),
+            music_playing: false,
+            music_lag: 0.0,
+        }
+    }
+
+    pub fn play(&mut self, sfx: Sfx) {
+        let sample_rate = self.sink.sample_rate();
+        let samples = self
+            .effects
+            .entry(sfx)
+            .or_insert_with(|| sfx.synthesize(sample_rate));
+        self.sink.play(samples);
+    }
+
+    // Keeps the music going, to the theme and tempo of the level.
+    pub fn play_music(&mut self, level: usize, danger: bool) {
+        self.music.set_level(level, danger);
+        self.music_playing = true;
+    }
+
+    pub fn stop_music(&mut self) {
+        self.music_playing = false;
+    }
+
+    pub fn advance(&mut self, delta: Duration) {
+        if self.music_playing {
+            self.music_lag += delta.as_secs_f64() * self.sink.sample_rate() as f64;
+            let len = self.music_lag as usize;
+            self.music_lag -= len as f64;
+            let samples = self.music.render(len, self.sink.sample_rate());
+            self.sink.stream(&samples);
+        }
+        self.sink.advance(delta);
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        self.sink.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tone() {
+        let mut samples = Vec::new();
+        Tone::new(Wave::Square, 441.0, 441.0, 10, 0.5).render(SAMPLE_RATE, &mut samples);
+
+        assert_eq!(samples.len(), 441);
+        assert!(samples.iter().all(|sample| sample.abs() <= 0.5));
+        // 100 samples per period, high for the first half of it
+        assert!(samples[10] > 0.0 && samples[60] < 0.0);
+    }
+
+    #[test]
+    fn test_synthesize() {
+        let effects = [
+            Sfx::Move,
+            Sfx::Rotate,
+            Sfx::Hold,
+            Sfx::Land,
+            Sfx::Match,
+            Sfx::Chain(2),
+            Sfx::LevelUp,
+            Sfx::GameOver,
+        ];
+        for sfx in effects {
+            let samples = sfx.synthesize(SAMPLE_RATE);
+            assert!(!samples.is_empty());
+            assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+        }
+        assert_ne!(Sfx::Chain(2).tones(), Sfx::Chain(3).tones());
+        assert_ne!(Sfx::Hold.tones(), Sfx::Move.tones());
+    }
+
+    #[test]
+    fn test_wav_sink() {
+        let mut sink = WavSink::new(PathBuf::new());
+        sink.play(&[0.5; 10]);
+        sink.advance(Duration::from_secs(1));
+        sink.play(&[0.25; 10]);
+        sink.play(&[0.25; 5]);
+
+        let samples = sink.samples();
+        assert_eq!(samples.len(), SAMPLE_RATE as usize + 10);
+        assert_eq!(samples[0], 0.5);
+        assert_eq!(samples[10], 0.0);
+        assert_eq!(samples[SAMPLE_RATE as usize], 0.5);
+        assert_eq!(samples[SAMPLE_RATE as usize + 9], 0.25);
+    }
+
+    #[test]
+    fn test_wav_sink_stream() {
+        let mut sink = WavSink::new(PathBuf::new());
+        sink.stream(&[0.5; 10]);
+        sink.stream(&[0.25; 10]);
+        sink.play(&[0.25; 5]);
+
+        let samples = sink.samples();
+        assert_eq!(samples.len(), 20);
+        assert_eq!(samples[0], 0.75);
+        assert_eq!(samples[10], 0.25);
+    }
+
+    #[test]
+    fn test_write_wav() {
+        let mut bytes = Vec::new();
+        write_wav(&mut bytes, SAMPLE_RATE, &[0.0, 1.0, -2.0]).unwrap();
+
+        assert_eq!(bytes.len(), 44 + 6);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(&bytes[8..16], b"WAVEfmt ");
+        assert_eq!(&bytes[44..], &[0, 0, 0xff, 0x7f, 0x01, 0x80]);
+    }
+
+    #[test]
+    fn test_audio_output() {
+        assert_eq!("device".parse(), Ok(AudioOutput::Device));
+        assert_eq!("off".parse(), Ok(AudioOutput::Off));
+        assert_eq!(
+            "out.wav".parse(),
+            Ok(AudioOutput::Wav(PathBuf::from("out.wav")))
+        );
+        assert_eq!("loud".parse::<AudioOutput>(), Err(()));
+    }
+}
@@ -0,0 +1,135 @@
+use crate::keymap::Action;
+use std::time::Duration;
+
+// Delayed auto-shift (DAS) and auto-repeat rate (ARR): an action held down
+// fires once, then again after `das` millis, then every `arr` millis.
+// Only of any use when the terminal reports key releases.
+#[derive(Debug, Clone)]
+pub struct AutoRepeat {
+    das: u64,
+    arr: u64,
+    // time left until the next repeat of every action held down
+    held: Vec<(Action, Duration)>,
+}
+
+impl AutoRepeat {
+    pub fn new(das: u64, arr: u64) -> Self {
+        Self {
+            das,
+            arr,
+            held: Vec::new(),
+        }
+    }
+
+    pub fn repeats(action: Action) -> bool {
+        matches!(
+            action,
+            Action::MoveLeft | Action::MoveRight | Action::SoftDrop
+        )
+    }
+
+    // Starts counting for the action, whose first move is up to the caller.
+    pub fn press(&mut self, action: Action) {
+        if !Self::repeats(action) || self.held.iter().any(|(held, _)| *held == action) {
+            return;
+        }
+        // the last direction pressed wins
+        let opposite = match action {
+            Action::MoveLeft => Some(Action::MoveRight),
+            Action::MoveRight => Some(Action::MoveLeft),
+            _ => None,
+        };
+        self.held.retain(|(held, _)| Some(*held) != opposite);
+        self.held.push((action, Duration::from_millis(self.das)));
+    }
+
+    pub fn release(&mut self, action: Action) {
+        self.held.retain(|(held, _)| *held != action);
+    }
+
+    // Forgets about every key, e.g. when the releases might get lost.
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+
+    // The actions to repeat this time around, as many times as they fell due:
+    // whatever time is left past a repeat counts towards the next one, so
+    // that repeats keep to `arr` whatever the frame rate. With no `arr` at
+    // all, an action repeats once every update.
+    pub fn update(&mut self, delta: Duration) -> Vec<Action> {
+        let arr = Duration::from_millis(self.arr);
+        let mut actions = Vec::new();
+        for (action, left) in self.held.iter_mut() {
+            let mut elapsed = delta;
+            while elapsed >= *left {
+                elapsed -= *left;
+                *left = arr;
+                actions.push(*action);
+                if arr.is_zero() {
+                    break;
+                }
+            }
+            *left = left.saturating_sub(elapsed);
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_das_and_arr() {
+        let mut auto_repeat = AutoRepeat::new(150, 50);
+        auto_repeat.press(Action::MoveLeft);
+        assert!(auto_repeat.update(Duration::from_millis(100)).is_empty());
+        assert_eq!(
+            auto_repeat.update(Duration::from_millis(50)),
+            vec![Action::MoveLeft]
+        );
+        assert!(auto_repeat.update(Duration::from_millis(40)).is_empty());
+        assert_eq!(
+            auto_repeat.update(Duration::from_millis(10)),
+            vec![Action::MoveLeft]
+        );
+
+        auto_repeat.release(Action::MoveLeft);
+        assert!(auto_repeat.update(Duration::from_millis(1000)).is_empty());
+    }
+
+    #[test]
+    fn test_arr_carries_over() {
+        let mut auto_repeat = AutoRepeat::new(0, 50);
+        auto_repeat.press(Action::MoveRight);
+        let frame = Duration::from_nanos(1_000_000_000 / 60);
+        // the first repeat right away, then one every 50ms: 20 in a second
+        let repeats: usize = (0..60).map(|_| auto_repeat.update(frame).len()).sum();
+        assert_eq!(repeats, 20);
+
+        // a long frame catches up with every repeat due
+        assert_eq!(auto_repeat.update(Duration::from_millis(130)).len(), 3);
+
+        let mut auto_repeat = AutoRepeat::new(0, 0);
+        auto_repeat.press(Action::SoftDrop);
+        assert_eq!(auto_repeat.update(frame), vec![Action::SoftDrop]);
+        assert_eq!(auto_repeat.update(frame), vec![Action::SoftDrop]);
+    }
+
+    #[test]
+    fn test_held_actions() {
+        let mut auto_repeat = AutoRepeat::new(100, 50);
+        auto_repeat.press(Action::Cycle);
+        auto_repeat.press(Action::MoveLeft);
+        auto_repeat.press(Action::SoftDrop);
+        // pressing the other way takes over
+        auto_repeat.press(Action::MoveRight);
+        assert_eq!(
+            auto_repeat.update(Duration::from_millis(100)),
+            vec![Action::SoftDrop, Action::MoveRight]
+        );
+
+        auto_repeat.clear();
+        assert!(auto_repeat.update(Duration::from_millis(1000)).is_empty());
+    }
+}
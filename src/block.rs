@@ -1,7 +1,12 @@
 use crate::frame::Pixel;
 use crossterm::style::Color;
+use std::{fmt, str::FromStr};
 
 const BLOCK_CHAR: char = '▓';
+const MAGIC_CHAR: char = '◆';
+const GHOST_CHAR: char = '░';
+// The magic jewel flashes through the colors of every other jewel.
+const MAGIC_COLORS: [u8; 6] = [226, 214, 196, 51, 46, 129];
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BlockKind {
@@ -9,11 +14,27 @@ pub enum BlockKind {
     Orange,
     Red,
     Cyan,
+    Green,
+    Purple,
+    Magic,
+}
+
+impl BlockKind {
+    // Every regular jewel, in the order they join the palette.
+    pub const JEWELS: [Self; 6] = [
+        Self::Yellow,
+        Self::Orange,
+        Self::Red,
+        Self::Cyan,
+        Self::Green,
+        Self::Purple,
+    ];
 }
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Block {
     kind: Option<BlockKind>,
+    phase: u8,
     pub exploding: bool,
 }
 
@@ -21,6 +42,7 @@ impl Block {
     pub fn new(kind: Option<BlockKind>) -> Self {
         Self {
             kind,
+            phase: 0,
             exploding: false,
         }
     }
@@ -55,19 +77,124 @@ impl Block {
                 color: Color::AnsiValue(51),
                 ..Pixel::default()
             },
+            Some(Green) => Pixel {
+                grapheme: BLOCK_CHAR,
+                color: Color::AnsiValue(46),
+                ..Pixel::default()
+            },
+            Some(Purple) => Pixel {
+                grapheme: BLOCK_CHAR,
+                color: Color::AnsiValue(129),
+                ..Pixel::default()
+            },
+            Some(Magic) => Pixel {
+                grapheme: MAGIC_CHAR,
+                color: Color::AnsiValue(MAGIC_COLORS[self.phase as usize % MAGIC_COLORS.len()]),
+                ..Pixel::default()
+            },
             None => Pixel::default(),
         }
     }
 
+    // Faint outline of the block, in its color but never exploding.
+    pub fn to_ghost_pixel(&self) -> Pixel {
+        if self.empty() {
+            return Pixel::default();
+        }
+        Pixel {
+            grapheme: GHOST_CHAR,
+            color: Block {
+                exploding: false,
+                ..*self
+            }
+            .to_pixel()
+            .color,
+            ..Pixel::default()
+        }
+    }
+
     pub fn update(&mut self, kind: Option<BlockKind>) {
         self.kind = kind;
     }
 
+    // Advances the animation of the block, only the magic jewel has any.
+    pub fn flash(&mut self) {
+        self.phase = self.phase.wrapping_add(1);
+    }
+
+    #[inline]
+    pub fn kind(&self) -> Option<BlockKind> {
+        self.kind
+    }
+
+    pub fn magic(&self) -> bool {
+        self.kind == Some(BlockKind::Magic)
+    }
+
     pub fn empty(&self) -> bool {
         self.kind.is_none()
     }
 }
 
+// Written in saved games as the initial of its kind, in upper case while
+// exploding, then the phase of its animation unless 0. `.` is no block.
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use BlockKind::*;
+        let initial = match self.kind {
+            Some(Yellow) => 'y',
+            Some(Orange) => 'o',
+            Some(Red) => 'r',
+            Some(Cyan) => 'c',
+            Some(Green) => 'g',
+            Some(Purple) => 'p',
+            Some(Magic) => 'm',
+            None if self.exploding => '*',
+            None => '.',
+        };
+        if self.exploding {
+            write!(f, "{}", initial.to_ascii_uppercase())?;
+        } else {
+            write!(f, "{initial}")?;
+        }
+        if self.phase > 0 {
+            write!(f, "{}", self.phase)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Block {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use BlockKind::*;
+        let mut chars = s.chars();
+        let initial = chars.next().ok_or(())?;
+        let kind = match initial.to_ascii_lowercase() {
+            'y' => Some(Yellow),
+            'o' => Some(Orange),
+            'r' => Some(Red),
+            'c' => Some(Cyan),
+            'g' => Some(Green),
+            'p' => Some(Purple),
+            'm' => Some(Magic),
+            '.' | '*' => None,
+            _ => return Err(()),
+        };
+        let phase = match chars.as_str() {
+            "" => 0,
+            phase => phase.parse().or(Err(()))?,
+        };
+
+        Ok(Self {
+            kind,
+            phase,
+            exploding: initial.is_ascii_uppercase() || initial == '*',
+        })
+    }
+}
+
 impl PartialEq for Block {
     fn eq(&self, other: &Self) -> bool {
         self.kind == other.kind
@@ -82,4 +209,62 @@ mod test {
     fn test_default() {
         assert!(Block::default().empty());
     }
+
+    #[test]
+    fn test_jewel_colors() {
+        let pixels: Vec<Pixel> = BlockKind::JEWELS
+            .iter()
+            .map(|kind| Block::new(Some(*kind)).to_pixel())
+            .collect();
+
+        for (i, pixel) in pixels.iter().enumerate() {
+            assert!(pixels[i + 1..].iter().all(|other| other != pixel));
+        }
+    }
+
+    #[test]
+    fn test_ghost() {
+        let mut block = Block::new(Some(BlockKind::Red));
+        block.exploding = true;
+        let ghost = block.to_ghost_pixel();
+
+        assert_eq!(ghost.grapheme, GHOST_CHAR);
+        assert_eq!(
+            ghost.color,
+            Block::new(Some(BlockKind::Red)).to_pixel().color
+        );
+        assert_eq!(Block::default().to_ghost_pixel(), Pixel::default());
+    }
+
+    #[test]
+    fn test_to_string_and_parse() {
+        let mut magic = Block::new(Some(BlockKind::Magic));
+        magic.flash();
+        magic.flash();
+        let mut red = Block::new(Some(BlockKind::Red));
+        red.exploding = true;
+
+        for block in [Block::default(), magic, red] {
+            let parsed: Block = block.to_string().parse().unwrap();
+            assert_eq!(parsed.kind, block.kind);
+            assert_eq!(parsed.phase, block.phase);
+            assert_eq!(parsed.exploding, block.exploding);
+        }
+        assert_eq!(magic.to_string(), "m2");
+        assert_eq!(red.to_string(), "R");
+        assert_eq!("x".parse::<Block>(), Err(()));
+        assert_eq!("".parse::<Block>(), Err(()));
+    }
+
+    #[test]
+    fn test_magic_flash() {
+        let mut block = Block::new(Some(BlockKind::Magic));
+        let pixel = block.to_pixel();
+        block.flash();
+
+        assert!(block.magic());
+        assert_eq!(pixel.grapheme, block.to_pixel().grapheme);
+        assert!(pixel != block.to_pixel());
+        assert_eq!(block, Block::new(Some(BlockKind::Magic)));
+    }
 }
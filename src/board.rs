@@ -1,50 +1,87 @@
 use crate::{
-    frame::{Drawable, Frame, Pixel},
-    NUM_ROWS,
+    frame::{draw_text, Drawable, Frame},
+    pit::PitSize,
 };
+use crossterm::style::Color;
 
 #[derive(Default)]
 pub struct Board {
     score: usize,
     blocks_score: usize,
+    level: usize,
+    chain: usize,
+    best: usize,
+    size: PitSize,
 }
 
 impl Board {
-    pub fn update(&mut self, score: usize, blocks_score: usize) {
+    pub fn new(size: PitSize) -> Self {
+        Self {
+            size,
+            ..Self::default()
+        }
+    }
+
+    pub fn update(&mut self, score: usize, blocks_score: usize, level: usize, chain: usize) {
         self.score = score;
         self.blocks_score = blocks_score;
+        self.level = level;
+        self.chain = chain;
+    }
+
+    // Best score of the ranking, to beat during the game.
+    pub fn set_best(&mut self, best: usize) {
+        self.best = best;
+    }
+
+    fn draw_text(frame: &mut Frame, y: usize, text: &str) {
+        draw_text(frame, 2, y, text, Color::White);
     }
 }
 
 impl Drawable for Board {
     fn draw(&self, frame: &mut Frame) {
-        for (pos, grapheme) in "Score".chars().enumerate() {
-            frame[2 + pos][NUM_ROWS - 6] = Pixel {
-                grapheme,
-                color: crossterm::style::Color::White,
-                ..Pixel::default()
-            };
-        }
-        for (pos, grapheme) in format!("{}", self.score).chars().enumerate() {
-            frame[2 + pos][NUM_ROWS - 5] = Pixel {
-                grapheme,
-                color: crossterm::style::Color::White,
-                ..Pixel::default()
-            };
-        }
-        for (pos, grapheme) in "Blocks".chars().enumerate() {
-            frame[2 + pos][NUM_ROWS - 3] = Pixel {
-                grapheme,
-                color: crossterm::style::Color::White,
-                ..Pixel::default()
-            };
-        }
-        for (pos, grapheme) in format!("{}", self.blocks_score).chars().enumerate() {
-            frame[2 + pos][NUM_ROWS - 2] = Pixel {
-                grapheme,
-                color: crossterm::style::Color::White,
-                ..Pixel::default()
-            };
+        // against the bottom of the pit, whatever its height
+        let rows = self.size.rows;
+        // a single match is no chain, only cascades are worth showing
+        if self.chain > 1 {
+            Self::draw_text(frame, rows - 12, "CHAIN");
+            Self::draw_text(frame, rows - 11, &format!("x{}", self.chain));
         }
+        Self::draw_text(frame, rows - 9, "Level");
+        Self::draw_text(frame, rows - 8, &format!("{}", self.level));
+        Self::draw_text(frame, rows - 6, "Score");
+        Self::draw_text(frame, rows - 5, &format!("{}", self.score));
+        let side_panel_x = self.size.side_panel_x();
+        draw_text(frame, side_panel_x, rows - 6, "Best", Color::White);
+        draw_text(
+            frame,
+            side_panel_x,
+            rows - 5,
+            &format!("{}", self.best.max(self.score)),
+            Color::White,
+        );
+        Self::draw_text(frame, rows - 3, "Blocks");
+        Self::draw_text(frame, rows - 2, &format!("{}", self.blocks_score));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::frame::new_frame;
+
+    #[test]
+    fn test_draw_tall_pit() {
+        let size = PitSize::new(6, 20);
+        let mut board = Board::new(size);
+        board.update(120, 3, 2, 0);
+        let mut frame = new_frame(size);
+        board.draw(&mut frame);
+
+        let line = |y: usize| -> String { frame[..8].iter().map(|col| col[y].grapheme).collect() };
+        assert_eq!(line(14).trim_end(), "  Score");
+        assert_eq!(line(15).trim_end(), "  120");
+        assert_eq!(line(18).trim_end(), "  3");
     }
 }
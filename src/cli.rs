@@ -0,0 +1,229 @@
+use crate::{
+    audio::AudioOutput, level::Level, mode::Mode, pit::PitSize, queue::Queue, rules::Rules,
+};
+use std::path::PathBuf;
+
+#[derive(Debug, PartialEq)]
+pub enum ArgsError {
+    MissingValue(String),
+    InvalidValue(String, String),
+    Unknown(String),
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub seed: Option<u64>,
+    pub level: Option<usize>,
+    pub next: Option<usize>,
+    pub mode: Mode,
+    pub pit: PitSize,
+    pub audio: AudioOutput,
+    pub render_music: Option<PathBuf>,
+    // where the games are recorded, and the one to play back instead
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+}
+
+impl Args {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, ArgsError> {
+        let mut parsed = Self::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = args.next().ok_or(ArgsError::MissingValue(arg.clone()))?;
+                    parsed.seed = Some(value.parse().or(Err(ArgsError::InvalidValue(arg, value)))?);
+                }
+                "--level" => {
+                    let value = args.next().ok_or(ArgsError::MissingValue(arg.clone()))?;
+                    parsed.level =
+                        Some(value.parse().or(Err(ArgsError::InvalidValue(arg, value)))?);
+                }
+                "--next" => {
+                    let value = args.next().ok_or(ArgsError::MissingValue(arg.clone()))?;
+                    parsed.next = value
+                        .parse()
+                        .ok()
+                        .filter(|next| (Queue::MIN_LEN..=Queue::MAX_LEN).contains(next))
+                        .map(Some)
+                        .ok_or(ArgsError::InvalidValue(arg, value))?;
+                }
+                "--mode" => {
+                    let value = args.next().ok_or(ArgsError::MissingValue(arg.clone()))?;
+                    parsed.mode = value.parse().or(Err(ArgsError::InvalidValue(arg, value)))?;
+                }
+                "--pit" => {
+                    let value = args.next().ok_or(ArgsError::MissingValue(arg.clone()))?;
+                    parsed.pit = value.parse().or(Err(ArgsError::InvalidValue(arg, value)))?;
+                }
+                "--audio" => {
+                    let value = args.next().ok_or(ArgsError::MissingValue(arg.clone()))?;
+                    parsed.audio = value.parse().or(Err(ArgsError::InvalidValue(arg, value)))?;
+                }
+                "--render-music" => {
+                    let value = args.next().ok_or(ArgsError::MissingValue(arg.clone()))?;
+                    parsed.render_music = Some(PathBuf::from(value));
+                }
+                "--record" => {
+                    let value = args.next().ok_or(ArgsError::MissingValue(arg.clone()))?;
+                    parsed.record = Some(PathBuf::from(value));
+                }
+                "--replay" => {
+                    let value = args.next().ok_or(ArgsError::MissingValue(arg.clone()))?;
+                    parsed.replay = Some(PathBuf::from(value));
+                }
+                _ => return Err(ArgsError::Unknown(arg)),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    // The level as it will be played, any number past the last one is the last one.
+    pub fn rules(&self) -> Rules {
+        Rules {
+            mode: self.mode,
+            level: Level::new(self.level.unwrap_or(Level::MIN)).number(),
+            next: self.next.unwrap_or(Queue::MIN_LEN),
+            pit: self.pit,
+            ..Rules::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, ArgsError> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_empty() {
+        assert_eq!(parse(&[]), Ok(Args::default()));
+    }
+
+    #[test]
+    fn test_parse_seed() {
+        assert_eq!(parse(&["--seed", "1234"]).unwrap().seed, Some(1234));
+        assert_eq!(
+            parse(&["--seed"]),
+            Err(ArgsError::MissingValue("--seed".to_string()))
+        );
+        assert_eq!(
+            parse(&["--seed", "abc"]),
+            Err(ArgsError::InvalidValue(
+                "--seed".to_string(),
+                "abc".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_level() {
+        let args = parse(&["--level", "3", "--seed", "7"]).unwrap();
+        assert_eq!(args.level, Some(3));
+        assert_eq!(args.seed, Some(7));
+        assert_eq!(args.rules().level, 3);
+        assert_eq!(parse(&["--level", "99"]).unwrap().rules().level, Level::MAX);
+        assert_eq!(parse(&["--level", "0"]).unwrap().rules().level, Level::MIN);
+        assert_eq!(
+            parse(&["--level", "-1"]),
+            Err(ArgsError::InvalidValue(
+                "--level".to_string(),
+                "-1".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!(parse(&[]).unwrap().mode, Mode::Easy);
+        assert_eq!(parse(&["--mode", "arcade"]).unwrap().mode, Mode::Arcade);
+        assert_eq!(
+            parse(&["--mode", "hard"]),
+            Err(ArgsError::InvalidValue(
+                "--mode".to_string(),
+                "hard".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_pit() {
+        assert_eq!(parse(&[]).unwrap().pit, PitSize::default());
+        assert_eq!(
+            parse(&["--pit", "8x16"]).unwrap().rules().pit,
+            PitSize { cols: 8, rows: 16 }
+        );
+        for value in ["8", "8x", "x16", "2x16", "8x4", "100x16"] {
+            assert_eq!(
+                parse(&["--pit", value]),
+                Err(ArgsError::InvalidValue(
+                    "--pit".to_string(),
+                    value.to_string()
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_next() {
+        assert_eq!(parse(&[]).unwrap().rules().next, Queue::MIN_LEN);
+        assert_eq!(parse(&["--next", "3"]).unwrap().rules().next, 3);
+        for value in ["0", "6", "abc"] {
+            assert_eq!(
+                parse(&["--next", value]),
+                Err(ArgsError::InvalidValue(
+                    "--next".to_string(),
+                    value.to_string()
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_audio() {
+        assert_eq!(parse(&[]).unwrap().audio, AudioOutput::Device);
+        assert_eq!(parse(&["--audio", "off"]).unwrap().audio, AudioOutput::Off);
+        assert_eq!(
+            parse(&["--audio", "loud"]),
+            Err(ArgsError::InvalidValue(
+                "--audio".to_string(),
+                "loud".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_render_music() {
+        assert_eq!(
+            parse(&["--render-music", "out.wav"]).unwrap().render_music,
+            Some(PathBuf::from("out.wav"))
+        );
+        assert_eq!(
+            parse(&["--render-music"]),
+            Err(ArgsError::MissingValue("--render-music".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_replay() {
+        let args = parse(&["--replay", "best.txt", "--record", "last.txt"]).unwrap();
+        assert_eq!(args.replay, Some(PathBuf::from("best.txt")));
+        assert_eq!(args.record, Some(PathBuf::from("last.txt")));
+        assert_eq!(
+            parse(&["--replay"]),
+            Err(ArgsError::MissingValue("--replay".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_unknown() {
+        assert_eq!(
+            parse(&["--foo"]),
+            Err(ArgsError::Unknown("--foo".to_string()))
+        );
+    }
+}
@@ -1,15 +1,16 @@
 use crate::{
-    block::{Block, BlockKind},
+    block::Block,
     frame::{Drawable, Frame},
-    pit::Heap,
+    pit::{Heap, PitSize},
     point,
+    save::{Persist, Save},
+    source::ColumnSource,
     timer::Timer,
-    Point, NUM_COLS, NUM_ROWS, PIT_STARTING_X,
+    Point, PIT_STARTING_X,
 };
-use rand::{distributions::Uniform, thread_rng, Rng};
 use std::time::Duration;
 
-type Shaft = [Block; 3];
+pub type Shaft = [Block; 3];
 
 const STARTING_X: usize = 2;
 const STARTING_Y: usize = 0;
@@ -20,29 +21,18 @@ pub struct Column {
     pos: Point,
     dropping: bool,
     move_timer: Timer,
-    pub stand_by: bool,
+    flash_timer: Timer,
 }
 
 impl Column {
     pub const MOVE_MILLIS: u64 = 1000;
+    pub const FLASH_MILLIS: u64 = 100;
+    // Points for every row dropped on purpose, rather than by gravity.
+    pub const SOFT_DROP_SCORE: usize = 1;
+    pub const HARD_DROP_SCORE: usize = 2;
 
-    pub fn new() -> Self {
-        let blocks = thread_rng()
-            .sample_iter(Uniform::<u8>::new_inclusive(1, 4))
-            .take(3)
-            .map(|index| -> Block {
-                let kind = match index {
-                    1 => Some(BlockKind::Yellow),
-                    2 => Some(BlockKind::Orange),
-                    3 => Some(BlockKind::Red),
-                    4 => Some(BlockKind::Cyan),
-                    _ => None,
-                };
-                Block::new(kind)
-            })
-            .collect::<Vec<Block>>();
-
-        Self::from([blocks[0], blocks[1], blocks[2]])
+    pub fn new(source: &mut dyn ColumnSource) -> Self {
+        Self::from(source.next_shaft())
     }
 
     pub fn from(shaft: Shaft) -> Self {
@@ -52,28 +42,67 @@ impl Column {
         }
     }
 
-    pub fn cycle(&mut self) {
+    // Puts the column back at the top center of the pit.
+    pub fn enter(&mut self, heap: &Heap) {
+        self.pos = point!((heap.cols() - 1) / 2, STARTING_Y);
+    }
+
+    pub fn set_move_millis(&mut self, millis: u64) {
+        self.move_timer = Timer::from_millis(millis);
+    }
+
+    pub fn shaft(&self) -> Shaft {
+        self.shaft
+    }
+
+    pub fn dropping(&self) -> bool {
+        self.dropping
+    }
+
+    // Cycles the blocks of the column, returns whether it could.
+    pub fn cycle(&mut self) -> bool {
         if self.dropping {
             self.shaft.rotate_right(1);
         }
+        self.dropping
     }
 
-    pub fn move_down(&mut self, heap: &Heap) {
-        if !self.detect_hit_downwards(heap) {
+    // Moves the column one row down, returns whether it could.
+    pub fn move_down(&mut self, heap: &Heap) -> bool {
+        if self.dropping && !self.detect_hit_downwards(heap) {
             self.pos.y += 1;
+            return true;
+        }
+        false
+    }
+
+    // Drops the column straight to its landing row, and locks it right away.
+    // Returns the number of rows it dropped.
+    pub fn hard_drop(&mut self, heap: &Heap) -> usize {
+        let mut rows = 0;
+        while self.move_down(heap) {
+            rows += 1;
         }
+        if self.dropping {
+            self.move_timer.finish();
+        }
+        rows
     }
 
-    pub fn move_left(&mut self, heap: &Heap) {
-        if !self.detect_hit_leftwards(heap) {
+    pub fn move_left(&mut self, heap: &Heap) -> bool {
+        if self.dropping && !self.detect_hit_leftwards(heap) {
             self.pos.x -= 1;
+            return true;
         }
+        false
     }
 
-    pub fn move_right(&mut self, heap: &Heap) {
-        if !self.detect_hit_rightwards(heap) {
+    pub fn move_right(&mut self, heap: &Heap) -> bool {
+        if self.dropping && !self.detect_hit_rightwards(heap) {
             self.pos.x += 1;
+            return true;
         }
+        false
     }
 
     pub fn detect_landing(&mut self, heap: &mut Heap, delta: Duration) -> Option<Vec<Point>> {
@@ -103,6 +132,12 @@ impl Column {
     }
 
     pub fn update(&mut self, heap: &Heap, delta: Duration) -> bool {
+        if self.flash_timer.update(delta).ready() {
+            self.flash_timer.reset();
+            for block in self.shaft.iter_mut() {
+                block.flash();
+            }
+        }
         if self.move_timer.update(delta).ready() {
             self.move_timer.reset();
             self.move_down(heap);
@@ -111,7 +146,34 @@ impl Column {
     }
 
     fn detect_hit_downwards(&self, heap: &Heap) -> bool {
-        self.dropping && (self.pos.y == NUM_ROWS - 1 || !heap[self.pos.x][self.pos.y + 1].empty())
+        self.dropping && Self::hit_downwards_at(heap, &self.pos)
+    }
+
+    fn hit_downwards_at(heap: &Heap, pos: &Point) -> bool {
+        pos.y == heap.rows() - 1 || !heap[pos.x][pos.y + 1].empty()
+    }
+
+    // Row the base of the column would land on, if dropped right away.
+    pub fn landing_y(&self, heap: &Heap) -> usize {
+        let mut pos = self.pos.clone();
+        while !Self::hit_downwards_at(heap, &pos) {
+            pos.y += 1;
+        }
+        pos.y
+    }
+
+    // Outline of the column where it would land, under the column itself.
+    pub fn draw_ghost(&self, heap: &Heap, frame: &mut Frame) {
+        if !self.dropping {
+            return;
+        }
+        let (x, y) = (self.pos.x + PIT_STARTING_X, self.landing_y(heap));
+        for (i, block) in self.shaft.iter().rev().enumerate() {
+            if i > y {
+                break;
+            }
+            frame[x][y - i] = block.to_ghost_pixel();
+        }
     }
 
     fn detect_hit_leftwards(&self, heap: &Heap) -> bool {
@@ -119,7 +181,8 @@ impl Column {
     }
 
     fn detect_hit_rightwards(&self, heap: &Heap) -> bool {
-        self.dropping && (self.pos.x == NUM_COLS - 1 || !heap[self.pos.x + 1][self.pos.y].empty())
+        self.dropping
+            && (self.pos.x == heap.cols() - 1 || !heap[self.pos.x + 1][self.pos.y].empty())
     }
 }
 
@@ -129,22 +192,44 @@ impl Default for Column {
             shaft: [Block::default(), Block::default(), Block::default()],
             pos: point!(STARTING_X, STARTING_Y),
             dropping: true,
-            stand_by: false,
             move_timer: Timer::from_millis(Column::MOVE_MILLIS),
+            flash_timer: Timer::from_millis(Column::FLASH_MILLIS),
         }
     }
 }
 
+impl Persist for Column {
+    fn persist(&self, save: &mut Save) {
+        save.set_list("column.shaft", &self.shaft);
+        save.set("column.pos", &self.pos);
+        save.set("column.dropping", self.dropping);
+        save.set("column.move_timer", self.move_timer);
+        save.set("column.flash_timer", self.flash_timer);
+    }
+
+    fn restore(save: &Save, size: PitSize) -> Option<Self> {
+        let shaft: Vec<Block> = save.get_list("column.shaft")?;
+        let pos: Point = save.get("column.pos")?;
+        if pos.x >= size.cols || pos.y >= size.rows {
+            return None;
+        }
+
+        Some(Self {
+            shaft: shaft.try_into().ok()?,
+            pos,
+            dropping: save.get("column.dropping")?,
+            move_timer: save.get("column.move_timer")?,
+            flash_timer: save.get("column.flash_timer")?,
+        })
+    }
+}
+
 impl Drawable for Column {
     fn draw(&self, frame: &mut Frame) {
         // Since it's already transfered to the heap of blocks,
         // we do not want to draw it on top unless it's still moving
         if self.dropping {
-            let (x, y) = if self.stand_by {
-                (PIT_STARTING_X - 2, 3)
-            } else {
-                (self.pos.x + PIT_STARTING_X, self.pos.y)
-            };
+            let (x, y) = (self.pos.x + PIT_STARTING_X, self.pos.y);
             for (i, block) in self.shaft.iter().rev().enumerate() {
                 if i > y {
                     // since it starts at y=0, do not draw the first two blocks as they would have negative y's
@@ -161,8 +246,10 @@ mod test {
     use crate::{
         block::{Block, BlockKind},
         column::{Column, STARTING_X, STARTING_Y},
-        pit::{Heap, Pit},
-        point, Point, NUM_ROWS,
+        pit::Heap,
+        point,
+        source::SeededSource,
+        Point, NUM_ROWS,
     };
     use std::time::Duration;
 
@@ -170,7 +257,8 @@ mod test {
 
     #[test]
     fn test_new() {
-        let col = Column::new();
+        let mut source = SeededSource::default();
+        let col = Column::new(&mut source);
 
         assert_eq!(col.pos.x, STARTING_X);
         assert_eq!(col.pos.y, STARTING_Y);
@@ -179,7 +267,7 @@ mod test {
         const MAX_COUNT: u8 = 5;
         let mut count_cmp = MAX_COUNT;
         loop {
-            let shaft_cmp = &Column::new().shaft;
+            let shaft_cmp = &Column::new(&mut source).shaft;
             let different = col
                 .shaft
                 .iter()
@@ -200,7 +288,7 @@ mod test {
 
     #[test]
     fn test_cycle() {
-        let mut col = Column::new();
+        let mut col = Column::new(&mut SeededSource::default());
         let shaft_copy = col.shaft;
         col.cycle();
         let shaft = col.shaft;
@@ -212,8 +300,8 @@ mod test {
 
     #[test]
     fn test_update() {
-        let heap = Pit::new_heap(None);
-        let mut col = Column::new();
+        let heap = Heap::default();
+        let mut col = Column::new(&mut SeededSource::default());
 
         col.update(&heap, Duration::from_millis(Column::MOVE_MILLIS - 1));
         assert_eq!(col.pos.y, 0);
@@ -221,10 +309,94 @@ mod test {
         assert_eq!(col.pos.y, 1);
     }
 
+    #[test]
+    fn test_update_with_move_millis() {
+        let heap = Heap::default();
+        let mut col = Column::new(&mut SeededSource::default());
+        col.set_move_millis(100);
+
+        col.update(&heap, Duration::from_millis(99));
+        assert_eq!(col.pos.y, 0);
+        col.update(&heap, Duration::from_millis(1));
+        assert_eq!(col.pos.y, 1);
+        col.update(&heap, Duration::from_millis(100));
+        assert_eq!(col.pos.y, 2);
+    }
+
+    #[test]
+    fn test_enter() {
+        let mut col = Column::new(&mut SeededSource::default());
+        col.move_down(&Heap::default());
+        col.enter(&Heap::default());
+        assert_eq!(col.pos, point!(STARTING_X, STARTING_Y));
+
+        col.enter(&Heap::new(8, 16, None));
+        assert_eq!(col.pos, point!(3, STARTING_Y));
+    }
+
+    #[test]
+    fn test_landing_in_wide_pit() {
+        let mut heap = Heap::new(8, 16, None);
+        let mut col = Column::new(&mut SeededSource::default());
+        col.enter(&heap);
+
+        for _ in 0..10 {
+            col.move_right(&heap);
+        }
+        assert_eq!(col.pos.x, 7);
+        assert_eq!(col.hard_drop(&heap), 15);
+        assert_eq!(
+            col.detect_landing(&mut heap, DELTA),
+            Some(vec![point!(7, 15), point!(7, 14), point!(7, 13)])
+        );
+    }
+
+    #[test]
+    fn test_move_down() {
+        let mut heap = Heap::default();
+        let mut col = Column::new(&mut SeededSource::default());
+
+        assert!(col.move_down(&heap));
+        assert_eq!(col.pos.y, STARTING_Y + 1);
+
+        heap[STARTING_X][STARTING_Y + 2] = Block::new(Some(BlockKind::Cyan));
+
+        assert!(!col.move_down(&heap));
+        assert_eq!(col.pos.y, STARTING_Y + 1);
+    }
+
+    #[test]
+    fn test_hard_drop() {
+        let mut heap = Heap::default();
+        let mut col = Column::new(&mut SeededSource::default());
+
+        assert_eq!(col.hard_drop(&heap), NUM_ROWS - 1);
+        assert_eq!(col.pos.y, NUM_ROWS - 1);
+        // locks instantly, without waiting for the move timer
+        assert!(col.detect_landing(&mut heap, Duration::ZERO).is_some());
+        // once landed, it can't be moved anymore
+        assert_eq!(col.hard_drop(&heap), 0);
+        col.move_left(&heap);
+        assert_eq!(col.pos.x, STARTING_X);
+    }
+
+    #[test]
+    fn test_landing_y() {
+        let mut heap = Heap::default();
+        let mut col = Column::new(&mut SeededSource::default());
+        assert_eq!(col.landing_y(&heap), NUM_ROWS - 1);
+
+        heap[STARTING_X][NUM_ROWS - 1] = Block::new(Some(BlockKind::Cyan));
+        assert_eq!(col.landing_y(&heap), NUM_ROWS - 2);
+        // as far as a hard drop takes it
+        assert_eq!(col.hard_drop(&heap), NUM_ROWS - 2);
+        assert_eq!(col.landing_y(&heap), col.pos.y);
+    }
+
     #[test]
     fn test_landing_on_heap() {
-        let mut heap: Heap = Pit::new_heap(None);
-        let mut col = Column::new();
+        let mut heap = Heap::default();
+        let mut col = Column::new(&mut SeededSource::default());
 
         assert_eq!(col.detect_landing(&mut heap, DELTA), None);
 
@@ -238,8 +410,8 @@ mod test {
 
     #[test]
     fn test_landing_reached_bottom() {
-        let mut heap: Heap = Pit::new_heap(None);
-        let mut col = Column::new();
+        let mut heap = Heap::default();
+        let mut col = Column::new(&mut SeededSource::default());
 
         assert_eq!(col.detect_landing(&mut heap, DELTA), None);
 
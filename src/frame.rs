@@ -1,8 +1,8 @@
 use crossterm::style::Color;
 
-use crate::{NUM_COLS, NUM_ROWS, PIT_STARTING_X};
+use crate::pit::PitSize;
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Pixel {
     pub grapheme: char,
     pub color: Color,
@@ -19,12 +19,42 @@ impl Default for Pixel {
     }
 }
 
-pub type Frame = [[Pixel; NUM_ROWS]; NUM_COLS + PIT_STARTING_X];
+pub type Frame = Vec<Vec<Pixel>>;
 
-pub fn new_frame() -> Frame {
-    [[Pixel::default(); NUM_ROWS]; NUM_COLS + PIT_STARTING_X]
+pub fn new_frame(size: PitSize) -> Frame {
+    vec![vec![Pixel::default(); size.frame_height()]; size.frame_width()]
 }
 
 pub trait Drawable {
     fn draw(&self, frame: &mut Frame);
 }
+
+// Writes the text from left to right, whatever does not fit in the frame is cut off.
+pub fn draw_text(frame: &mut Frame, x: usize, y: usize, text: &str, color: Color) {
+    for (pos, grapheme) in text.chars().enumerate() {
+        if let Some(pixel) = frame.get_mut(x + pos).and_then(|col| col.get_mut(y)) {
+            *pixel = Pixel {
+                grapheme,
+                color,
+                ..Pixel::default()
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_draw_text() {
+        let mut frame = new_frame(PitSize::default());
+        let (width, height) = (frame.len(), frame[0].len());
+        draw_text(&mut frame, width - 2, 1, "abc", Color::White);
+        draw_text(&mut frame, 0, height, "abc", Color::White);
+
+        assert_eq!(frame[width - 2][1].grapheme, 'a');
+        assert_eq!(frame[width - 1][1].grapheme, 'b');
+        assert_eq!(frame[width - 1][1].color, Color::White);
+    }
+}
@@ -0,0 +1,470 @@
+use crate::{
+    board::Board,
+    column::{Column, Shaft},
+    frame::{Drawable, Frame},
+    hold::Hold,
+    keymap::Action,
+    level::Level,
+    pit::{Pit, PitEvent, PitSize},
+    queue::Queue,
+    replay::Replay,
+    rules::Rules,
+    save::{Persist, Save},
+    source::{ColumnSource, SeededSource},
+};
+use std::{mem, time::Duration};
+
+// What happened during a step, for the frontend to show, play or count.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    Moved,
+    Cycled,
+    Held,
+    // landings, matches and chains, as the pit tells them
+    Pit(PitEvent),
+    LevelUp(usize),
+    GameOver,
+}
+
+// The simulation runs on ticks of a fixed length, whatever the frame rate,
+// so that the same inputs on the same ticks always play the same game.
+pub const TICKS_PER_SECOND: u64 = 60;
+
+// One game from the first column to the top out, with no knowledge of the
+// terminal: whoever drives it passes the actions of the player and the time
+// gone by, and gets back what happened.
+pub struct Game {
+    rules: Rules,
+    board: Board,
+    pit: Pit,
+    level: Level,
+    queue: Queue,
+    column: Column,
+    hold: Hold,
+    // game time, which stands still unless stepped
+    ticks: u64,
+    // time stepped but not simulated yet, less than a tick unless behind
+    lag: Duration,
+    // inputs waiting for the next tick
+    pending: Vec<Action>,
+    replay: Replay,
+}
+
+impl Game {
+    pub const TICK: Duration = Duration::from_nanos(1_000_000_000 / TICKS_PER_SECOND);
+    // Ticks simulated in a single step at most. Beyond, the game slows down
+    // rather than jumping ahead, e.g. after the process was suspended.
+    pub const MAX_CATCH_UP: u32 = 15;
+
+    pub fn new(seed: Option<u64>, rules: Rules, best: usize) -> Self {
+        let level = Level::new(rules.level);
+        let mut source = seed.map_or_else(SeededSource::default, SeededSource::new);
+        source.set_colors(rules.mode.colors());
+        if !rules.magic {
+            source.set_magic_probability(0.0);
+        }
+        let pit = Pit::new(rules.pit);
+        let mut queue = Queue::new(source, rules.next, rules.pit);
+        let mut column = Column::new(&mut queue);
+        column.enter(&pit.heap);
+        column.set_move_millis(level.gravity_millis());
+        let mut board = Board::new(rules.pit);
+        board.set_best(best);
+        let replay = Replay::new(queue.seed(), rules);
+
+        Self {
+            rules,
+            board,
+            pit,
+            level,
+            queue,
+            column,
+            hold: Hold::new(rules.pit),
+            ticks: 0,
+            lag: Duration::ZERO,
+            pending: Vec::new(),
+            replay,
+        }
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.queue.seed()
+    }
+
+    pub fn pit(&self) -> &Pit {
+        &self.pit
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn level(&self) -> usize {
+        self.level.number()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(1_000_000_000 / TICKS_PER_SECOND * self.ticks)
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn over(&self) -> bool {
+        self.pit.topped_up()
+    }
+
+    // The game so far, as a replay that ends now.
+    pub fn replay(&self) -> Replay {
+        let mut replay = self.replay.clone();
+        replay.finish(self.ticks);
+        replay
+    }
+
+    // Puts the game aside, to be resumed on the very same tick.
+    pub fn save(&self) -> Save {
+        let mut save = Save::default();
+        self.rules.persist(&mut save);
+        save.set("game.ticks", self.ticks);
+        save.set("game.lag", self.lag.as_nanos());
+        self.level.persist(&mut save);
+        self.pit.persist(&mut save);
+        self.column.persist(&mut save);
+        self.queue.persist(&mut save);
+        self.hold.persist(&mut save);
+        // so that the replay of the game is the whole of it
+        for (tick, action) in self.replay.inputs() {
+            save.set("replay.input", format!("{tick} {}", action.name()));
+        }
+        save
+    }
+
+    // Resumes a saved game, unless anything is missing or out of place.
+    pub fn restore(save: &Save, best: usize) -> Option<Self> {
+        let rules = Rules::restore(save, PitSize::default())?;
+        let size = rules.pit;
+        let level = Level::restore(save, size)?;
+        let pit = Pit::restore(save, size)?;
+        let queue = Queue::restore(save, size)?;
+        let mut replay = Replay::new(queue.seed(), rules);
+        for input in save.all("replay.input") {
+            let (tick, action) = input.split_once(' ')?;
+            replay.record(tick.parse().ok()?, action.parse().ok()?);
+        }
+        let mut board = Board::new(size);
+        board.set_best(best);
+        board.update(pit.score(), pit.blocks_score(), level.number(), pit.chain());
+
+        Some(Self {
+            rules,
+            board,
+            pit,
+            level,
+            queue,
+            column: Column::restore(save, size)?,
+            hold: Hold::restore(save, size)?,
+            ticks: save.get("game.ticks")?,
+            lag: Duration::from_nanos(save.get("game.lag")?),
+            pending: Vec::new(),
+            replay,
+        })
+    }
+
+    // Runs as many ticks as the time gone by is worth, the inputs being
+    // performed on the next one, in this step or a later one.
+    pub fn step(&mut self, inputs: &[Action], delta: Duration) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        self.pending.extend_from_slice(inputs);
+        self.lag += delta;
+        for _ in 0..Self::MAX_CATCH_UP {
+            if self.lag < Self::TICK {
+                break;
+            }
+            self.lag -= Self::TICK;
+            let inputs = mem::take(&mut self.pending);
+            events.extend(self.tick(&inputs));
+        }
+        // too far behind to ever catch up
+        if self.lag >= Self::TICK {
+            self.lag = Duration::ZERO;
+        }
+        events
+    }
+
+    // Performs the actions, then lets the columns fall and the jewels clear
+    // for a tick. Once the game is over, nothing happens anymore.
+    pub fn tick(&mut self, inputs: &[Action]) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        if self.over() {
+            return events;
+        }
+        for action in inputs {
+            self.perform(*action, &mut events);
+        }
+        self.update(Self::TICK, &mut events);
+        self.ticks += 1;
+        if self.over() {
+            events.push(GameEvent::GameOver);
+        }
+        events
+    }
+
+    // Moves, drops and cycles the column, as the player asks.
+    fn perform(&mut self, action: Action, events: &mut Vec<GameEvent>) {
+        self.replay.record(self.ticks, action);
+        match action {
+            Action::MoveLeft if self.column.move_left(&self.pit.heap) => {
+                events.push(GameEvent::Moved);
+            }
+            Action::MoveRight if self.column.move_right(&self.pit.heap) => {
+                events.push(GameEvent::Moved);
+            }
+            Action::SoftDrop => {
+                let rows = usize::from(self.column.move_down(&self.pit.heap));
+                self.pit.reward(rows * Column::SOFT_DROP_SCORE);
+            }
+            Action::HardDrop => {
+                let rows = self.column.hard_drop(&self.pit.heap);
+                self.pit.reward(rows * Column::HARD_DROP_SCORE);
+            }
+            Action::Cycle if self.column.cycle() => {
+                events.push(GameEvent::Cycled);
+            }
+            Action::Hold if self.rules.hold && self.hold.available() && self.column.dropping() => {
+                let shaft = self.hold.swap(self.column.shaft());
+                let shaft = shaft.unwrap_or_else(|| self.queue.next_shaft());
+                self.renew_column(shaft);
+                events.push(GameEvent::Held);
+            }
+            _ => {}
+        }
+    }
+
+    // Brings in a column at the top of the pit.
+    fn renew_column(&mut self, shaft: Shaft) {
+        self.column = Column::from(shaft);
+        self.column.enter(&self.pit.heap);
+        self.column.set_move_millis(self.level.gravity_millis());
+    }
+
+    fn update(&mut self, delta: Duration, events: &mut Vec<GameEvent>) {
+        let pit_events = self.pit.update(&mut self.column, delta);
+        events.extend(pit_events.into_iter().map(GameEvent::Pit));
+        let (score, blocks_score) = (self.pit.score(), self.pit.blocks_score());
+        // move column down if dropping, otherwise create a new one
+        if self.pit.stable() {
+            let dropping = self.column.update(&self.pit.heap, delta);
+            // if the column landed already, renew it
+            if !dropping {
+                let shaft = self.queue.next_shaft();
+                self.renew_column(shaft);
+                self.hold.reset();
+            }
+        }
+        // the more jewels cleared, the faster the columns fall
+        if self.level.update(blocks_score) {
+            events.push(GameEvent::LevelUp(self.level.number()));
+        }
+        // keep track of scores, etc. in the board
+        self.board
+            .update(score, blocks_score, self.level.number(), self.pit.chain());
+    }
+}
+
+impl Drawable for Game {
+    fn draw(&self, frame: &mut Frame) {
+        self.board.draw(frame);
+        self.pit.draw(frame);
+        if self.rules.ghost {
+            self.column.draw_ghost(&self.pit.heap, frame);
+        }
+        self.column.draw(frame);
+        self.queue.draw(frame);
+        if self.rules.hold {
+            self.hold.draw(frame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::frame::new_frame;
+
+    // Drops every column straight down the middle, until the top out.
+    fn play_out(game: &mut Game) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        for _ in 0..10_000 {
+            events.extend(game.tick(&[Action::HardDrop]));
+            if game.over() {
+                break;
+            }
+        }
+        events
+    }
+
+    #[test]
+    fn test_play_out() {
+        let mut game = Game::new(Some(3), Rules::default(), 0);
+        let events = play_out(&mut game);
+
+        assert!(game.over());
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, GameEvent::Pit(PitEvent::ColumnLanded { .. }))));
+        assert_eq!(
+            events[events.len() - 2..],
+            [GameEvent::Pit(PitEvent::ToppedOut), GameEvent::GameOver]
+        );
+        assert!(game.tick(&[Action::MoveLeft]).is_empty());
+    }
+
+    #[test]
+    fn test_same_inputs_same_game() {
+        let mut game = Game::new(Some(3), Rules::default(), 0);
+        let mut game_cmp = Game::new(Some(3), Rules::default(), 0);
+
+        assert_eq!(play_out(&mut game), play_out(&mut game_cmp));
+        assert_eq!(game.pit().score(), game_cmp.pit().score());
+        assert_eq!(game.elapsed(), game_cmp.elapsed());
+    }
+
+    #[test]
+    fn test_frame_rate_independent() {
+        let mut game = Game::new(Some(5), Rules::default(), 0);
+        let mut game_cmp = Game::new(Some(5), Rules::default(), 0);
+        // jittery frames on the one hand, one tick at a time on the other
+        for i in 0..600 {
+            let delta = Duration::from_millis([3, 40, 16, 21][i % 4]);
+            game.step(&[], delta);
+        }
+        while game_cmp.ticks() < game.ticks() {
+            game_cmp.tick(&[]);
+        }
+
+        let size = Rules::default().pit;
+        let (mut frame, mut frame_cmp) = (new_frame(size), new_frame(size));
+        game.draw(&mut frame);
+        game_cmp.draw(&mut frame_cmp);
+        assert_eq!(frame, frame_cmp);
+        assert_eq!(game.pit().score(), game_cmp.pit().score());
+    }
+
+    #[test]
+    fn test_step() {
+        let mut game = Game::new(Some(3), Rules::default(), 0);
+        game.step(&[], Game::TICK * 10 + Game::TICK / 2);
+        assert_eq!(game.ticks(), 10);
+
+        // inputs wait for the next tick
+        assert!(game.step(&[Action::MoveLeft], Game::TICK / 4).is_empty());
+        assert_eq!(game.step(&[], Game::TICK / 2), vec![GameEvent::Moved]);
+        assert_eq!(game.replay().inputs(), &[(10, Action::MoveLeft)]);
+
+        // far behind, the rest is dropped
+        game.step(&[], Duration::from_secs(3600));
+        assert_eq!(game.ticks(), 11 + u64::from(Game::MAX_CATCH_UP));
+        game.step(&[], Duration::ZERO);
+        assert_eq!(game.ticks(), 11 + u64::from(Game::MAX_CATCH_UP));
+
+        // just as many ticks as caught up with, the time left is kept
+        let mut game = Game::new(Some(3), Rules::default(), 0);
+        game.step(&[], Game::TICK * Game::MAX_CATCH_UP + Game::TICK / 2);
+        assert_eq!(game.ticks(), u64::from(Game::MAX_CATCH_UP));
+        game.step(&[], Game::TICK / 2);
+        assert_eq!(game.ticks(), u64::from(Game::MAX_CATCH_UP) + 1);
+    }
+
+    #[test]
+    fn test_perform() {
+        let mut game = Game::new(Some(3), Rules::default(), 0);
+        assert_eq!(
+            game.tick(&[Action::MoveLeft, Action::Cycle]),
+            vec![GameEvent::Moved, GameEvent::Cycled]
+        );
+        assert_eq!(game.tick(&[Action::Hold]), vec![GameEvent::Held]);
+        // once per landing
+        assert!(game.tick(&[Action::Hold]).is_empty());
+
+        let replay = game.replay();
+        assert_eq!(replay.seed, 3);
+        assert_eq!(replay.inputs().len(), 4);
+        assert_eq!(replay.end(), Some(game.ticks()));
+    }
+
+    // Some moves to scatter the columns around the pit.
+    fn inputs(tick: u64) -> Vec<Action> {
+        match tick % 120 {
+            0 => vec![Action::MoveLeft, Action::MoveLeft],
+            60 => vec![Action::MoveRight],
+            70 => vec![Action::Hold],
+            20 | 80 => vec![Action::Cycle],
+            30 | 90 => vec![Action::HardDrop],
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_save_and_restore() {
+        let rules = Rules {
+            next: 3,
+            ..Rules::default()
+        };
+        let mut game = Game::new(Some(1), rules, 0);
+        // halfway through the blinking of the jewels being cleared
+        while game.pit.chain() == 0 || game.pit.stable() {
+            game.tick(&inputs(game.ticks()));
+        }
+        for _ in 0..5 {
+            game.tick(&[]);
+        }
+        game.step(&[], Game::TICK / 3);
+
+        let save = Save::parse(&game.save().serialize()).unwrap();
+        let mut resumed = Game::restore(&save, 0).unwrap();
+        assert_eq!(resumed.save(), save);
+
+        while !game.over() && game.ticks() < 3000 {
+            let inputs = inputs(game.ticks());
+            assert_eq!(game.tick(&inputs), resumed.tick(&inputs));
+        }
+        let size = rules.pit;
+        let (mut frame, mut frame_cmp) = (new_frame(size), new_frame(size));
+        game.draw(&mut frame);
+        resumed.draw(&mut frame_cmp);
+        assert_eq!(frame, frame_cmp);
+        assert_eq!(resumed.replay(), game.replay());
+    }
+
+    #[test]
+    fn test_restore_broken() {
+        let mut game = Game::new(Some(1), Rules::default(), 0);
+        game.tick(&[Action::MoveLeft]);
+        let contents = game.save().serialize();
+
+        for (from, to) in [
+            ("pit.stage=stable", "pit.stage=melting"),
+            ("column.pos=1:", "column.pos=9:"),
+            ("level.current=1", "level.current=99"),
+        ] {
+            assert!(contents.contains(from));
+            let save = Save::parse(&contents.replace(from, to)).unwrap();
+            assert!(Game::restore(&save, 0).is_none());
+        }
+    }
+
+    #[test]
+    fn test_hold_rule() {
+        let rules = Rules {
+            hold: false,
+            ..Rules::default()
+        };
+        let mut game = Game::new(Some(3), rules, 0);
+        assert!(game.tick(&[Action::Hold]).is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+use crate::{
+    frame::{draw_text, Drawable, Frame, Pixel},
+    pit::PitSize,
+    ranking::{today, Entry, Ranking},
+};
+use crossterm::style::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameOverChoice {
+    PlayAgain,
+    Quit,
+}
+
+// Final results of a game, and what to do next.
+pub struct GameOver {
+    score: usize,
+    blocks_score: usize,
+    level: usize,
+    max_chain: usize,
+    // all it takes to play the same sequence of columns again
+    seed: u64,
+    choice: GameOverChoice,
+    // initials being typed in, while a new record is entered in the ranking
+    initials: Option<String>,
+    size: PitSize,
+}
+
+impl GameOver {
+    pub fn new(
+        size: PitSize,
+        score: usize,
+        blocks_score: usize,
+        level: usize,
+        max_chain: usize,
+        seed: u64,
+    ) -> Self {
+        Self {
+            size,
+            score,
+            blocks_score,
+            level,
+            max_chain,
+            seed,
+            choice: GameOverChoice::PlayAgain,
+            initials: None,
+        }
+    }
+
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    // Asks for the initials of the player before offering any choice.
+    pub fn prompt_initials(&mut self) {
+        self.initials = Some(String::new());
+    }
+
+    #[inline]
+    pub fn prompting(&self) -> bool {
+        self.initials.is_some()
+    }
+
+    pub fn type_initial(&mut self, c: char) {
+        if let Some(initials) = self.initials.as_mut() {
+            if c.is_ascii_alphanumeric() && initials.len() < Ranking::MAX_INITIALS {
+                initials.push(c.to_ascii_uppercase());
+            }
+        }
+    }
+
+    pub fn erase_initial(&mut self) {
+        if let Some(initials) = self.initials.as_mut() {
+            initials.pop();
+        }
+    }
+
+    // Ends the prompt without entering the score in the ranking.
+    pub fn cancel_initials(&mut self) {
+        self.initials = None;
+    }
+
+    // Ends the prompt, returns the new entry for the ranking.
+    pub fn confirm_initials(&mut self) -> Option<Entry> {
+        if self.initials.as_ref().is_some_and(String::is_empty) {
+            return None;
+        }
+        self.initials.take().map(|initials| Entry {
+            initials,
+            score: self.score,
+            jewels: self.blocks_score,
+            level: self.level,
+            date: today(),
+        })
+    }
+
+    // Toggles between the only two choices.
+    pub fn select_next(&mut self) {
+        self.choice = match self.choice {
+            GameOverChoice::PlayAgain => GameOverChoice::Quit,
+            GameOverChoice::Quit => GameOverChoice::PlayAgain,
+        };
+    }
+
+    #[inline]
+    pub fn choice(&self) -> GameOverChoice {
+        self.choice
+    }
+}
+
+impl Drawable for GameOver {
+    fn draw(&self, frame: &mut Frame) {
+        for col in frame.iter_mut() {
+            col.fill(Pixel::default());
+        }
+        draw_text(frame, 3, 1, "GAME OVER", Color::AnsiValue(196));
+        draw_text(frame, 1, 3, &format!("Score  {}", self.score), Color::White);
+        draw_text(
+            frame,
+            1,
+            4,
+            &format!("Jewels {}", self.blocks_score),
+            Color::White,
+        );
+        draw_text(
+            frame,
+            1,
+            5,
+            &format!("Chain  x{}", self.max_chain),
+            Color::White,
+        );
+        draw_text(frame, 1, 6, &format!("Seed   {}", self.seed), Color::Grey);
+
+        if let Some(initials) = &self.initials {
+            draw_text(frame, 1, 7, "NEW RECORD!", Color::AnsiValue(226));
+            draw_text(frame, 1, 8, &format!("Initials {initials}_"), Color::White);
+            return;
+        }
+
+        for (i, (choice, label)) in [
+            (GameOverChoice::PlayAgain, "Play again"),
+            (GameOverChoice::Quit, "Quit"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let (marker, color) = if *choice == self.choice {
+                ('>', Color::AnsiValue(226))
+            } else {
+                (' ', Color::White)
+            };
+            draw_text(
+                frame,
+                1,
+                self.size.rows - 4 + i,
+                &format!("{marker} {label}"),
+                color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::frame::new_frame;
+
+    #[test]
+    fn test_select_next() {
+        let mut game_over = GameOver::new(PitSize::default(), 100, 10, 1, 2, 7);
+        assert_eq!(game_over.choice(), GameOverChoice::PlayAgain);
+        game_over.select_next();
+        assert_eq!(game_over.choice(), GameOverChoice::Quit);
+        game_over.select_next();
+        assert_eq!(game_over.choice(), GameOverChoice::PlayAgain);
+    }
+
+    #[test]
+    fn test_draw() {
+        // the choices at the bottom, however tall the pit
+        let size = PitSize::new(8, 20);
+        let mut frame = new_frame(size);
+        GameOver::new(size, 1234, 56, 1, 3, 987654321).draw(&mut frame);
+
+        let line = |y: usize| -> String { frame.iter().map(|col| col[y].grapheme).collect() };
+        assert!(line(3).contains("1234"));
+        assert!(line(4).contains("56"));
+        assert!(line(5).contains("x3"));
+        assert!(line(6).contains("Seed   987654321"));
+        assert!(line(16).contains("> Play again"));
+    }
+
+    #[test]
+    fn test_initials() {
+        let mut game_over = GameOver::new(PitSize::default(), 1234, 56, 2, 3, 7);
+        assert!(!game_over.prompting());
+        assert_eq!(game_over.confirm_initials(), None);
+
+        game_over.prompt_initials();
+        assert!(game_over.prompting());
+        assert_eq!(game_over.confirm_initials(), None);
+        for c in ['a', '-', 'b', 'c', 'd'] {
+            game_over.type_initial(c);
+        }
+        game_over.erase_initial();
+        game_over.type_initial('z');
+
+        let entry = game_over.confirm_initials().unwrap();
+        assert_eq!(entry.initials, "ABZ");
+        assert_eq!((entry.score, entry.jewels, entry.level), (1234, 56, 2));
+        assert!(!game_over.prompting());
+
+        game_over.prompt_initials();
+        game_over.type_initial('x');
+        game_over.cancel_initials();
+        assert!(!game_over.prompting());
+        assert_eq!(game_over.confirm_initials(), None);
+    }
+}
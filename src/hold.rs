@@ -0,0 +1,105 @@
+use crate::{
+    column::Shaft,
+    frame::{draw_text, Drawable, Frame},
+    pit::PitSize,
+    save::{parse_list, Persist, Save},
+};
+use crossterm::style::Color;
+
+// Slot where a column can be set aside for later, once per landing.
+#[derive(Debug, Clone, Default)]
+pub struct Hold {
+    shaft: Option<Shaft>,
+    used: bool,
+    size: PitSize,
+}
+
+impl Hold {
+    pub fn new(size: PitSize) -> Self {
+        Self {
+            size,
+            ..Self::default()
+        }
+    }
+
+    pub fn available(&self) -> bool {
+        !self.used
+    }
+
+    // Stashes the shaft, handing out the one held before, if any.
+    pub fn swap(&mut self, shaft: Shaft) -> Option<Shaft> {
+        self.used = true;
+        self.shaft.replace(shaft)
+    }
+
+    // A new column is in, the slot may be used again.
+    pub fn reset(&mut self) {
+        self.used = false;
+    }
+
+    pub fn shaft(&self) -> Option<&Shaft> {
+        self.shaft.as_ref()
+    }
+}
+
+impl Persist for Hold {
+    fn persist(&self, save: &mut Save) {
+        if let Some(shaft) = self.shaft {
+            save.set_list("hold.shaft", &shaft);
+        }
+        save.set("hold.used", self.used);
+    }
+
+    fn restore(save: &Save, size: PitSize) -> Option<Self> {
+        let shaft = match save.all("hold.shaft").next() {
+            Some(shaft) => Some(parse_list(shaft)?.try_into().ok()?),
+            None => None,
+        };
+
+        Some(Self {
+            shaft,
+            used: save.get("hold.used")?,
+            size,
+        })
+    }
+}
+
+impl Drawable for Hold {
+    fn draw(&self, frame: &mut Frame) {
+        // on the right of the queue, which is never wider than 10 cells
+        let x = self.size.side_panel_x() + 10;
+        let color = if self.used {
+            Color::DarkGrey
+        } else {
+            Color::White
+        };
+        draw_text(frame, x, 1, "Hold", color);
+        if let Some(shaft) = self.shaft {
+            for (y, block) in shaft.iter().enumerate() {
+                frame[x + 1][2 + y] = block.to_pixel();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::block::{Block, BlockKind};
+
+    #[test]
+    fn test_swap() {
+        let red = [Block::new(Some(BlockKind::Red)); 3];
+        let cyan = [Block::new(Some(BlockKind::Cyan)); 3];
+        let mut hold = Hold::default();
+
+        assert!(hold.available());
+        assert_eq!(hold.swap(red), None);
+        assert!(!hold.available());
+        assert_eq!(hold.shaft(), Some(&red));
+
+        hold.reset();
+        assert_eq!(hold.swap(cyan), Some(red));
+        assert_eq!(hold.shaft(), Some(&cyan));
+    }
+}
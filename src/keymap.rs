@@ -0,0 +1,412 @@
+use crate::xdg;
+use crossterm::event::KeyCode;
+use std::{fs, io, path::PathBuf, str::FromStr};
+
+const FILE_NAME: &str = "keys.txt";
+
+// Everything the player can do while playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    Cycle,
+    Hold,
+    Pause,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Self; 8] = [
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::SoftDrop,
+        Self::HardDrop,
+        Self::Cycle,
+        Self::Hold,
+        Self::Pause,
+        Self::Quit,
+    ];
+
+    // Name in the config file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MoveLeft => "move_left",
+            Self::MoveRight => "move_right",
+            Self::SoftDrop => "soft_drop",
+            Self::HardDrop => "hard_drop",
+            Self::Cycle => "cycle",
+            Self::Hold => "hold",
+            Self::Pause => "pause",
+            Self::Quit => "quit",
+        }
+    }
+
+    // Name on screen.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::MoveLeft => "Left",
+            Self::MoveRight => "Right",
+            Self::SoftDrop => "Soft drop",
+            Self::HardDrop => "Hard drop",
+            Self::Cycle => "Cycle",
+            Self::Hold => "Hold",
+            Self::Pause => "Pause",
+            Self::Quit => "Quit",
+        }
+    }
+
+    fn index(&self) -> usize {
+        Self::ALL.iter().position(|action| action == self).unwrap()
+    }
+}
+
+// Parses the name in the config file.
+impl FromStr for Action {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.name() == s)
+            .ok_or(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Preset {
+    Arrows,
+    Wasd,
+    Vim,
+}
+
+impl Preset {
+    pub const ALL: [Self; 3] = [Self::Arrows, Self::Wasd, Self::Vim];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Arrows => "arrows",
+            Self::Wasd => "wasd",
+            Self::Vim => "vim",
+        }
+    }
+
+    pub fn keymap(&self) -> Keymap {
+        let (left, right, down, up) = match self {
+            Self::Arrows => (KeyCode::Left, KeyCode::Right, KeyCode::Down, KeyCode::Up),
+            Self::Wasd => (
+                KeyCode::Char('a'),
+                KeyCode::Char('d'),
+                KeyCode::Char('s'),
+                KeyCode::Char('w'),
+            ),
+            Self::Vim => (
+                KeyCode::Char('h'),
+                KeyCode::Char('l'),
+                KeyCode::Char('j'),
+                KeyCode::Char('k'),
+            ),
+        };
+
+        Keymap {
+            keys: [
+                vec![left],
+                vec![right],
+                vec![down],
+                vec![up],
+                vec![KeyCode::Char(' '), KeyCode::Enter],
+                vec![KeyCode::Char('c'), KeyCode::Tab],
+                vec![KeyCode::Char('p')],
+                vec![KeyCode::Esc],
+            ],
+            das: Keymap::DAS_MILLIS,
+            arr: Keymap::ARR_MILLIS,
+        }
+    }
+}
+
+// Keys of every action, and how fast held ones repeat, read from and saved
+// to a config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    keys: [Vec<KeyCode>; Action::ALL.len()],
+    pub das: u64,
+    pub arr: u64,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Preset::Arrows.keymap()
+    }
+}
+
+impl Keymap {
+    pub const VERSION: u32 = 1;
+    pub const DAS_MILLIS: u64 = 170;
+    pub const ARR_MILLIS: u64 = 50;
+    pub const MAX_MILLIS: u64 = 1000;
+
+    // Where the keymap lives.
+    pub fn path() -> Option<PathBuf> {
+        xdg::config_file(FILE_NAME)
+    }
+
+    // A missing or unreadable keymap is just the default one.
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| Self::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path().ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no config directory for the keymap",
+        ))?;
+        xdg::write(&path, self.serialize())
+    }
+
+    // Starts from the preset, if any, then every `action=key,key` line
+    // replaces the keys of the action. Whatever is not understood is skipped,
+    // but the keys of a newer version may mean something else altogether.
+    pub fn parse(contents: &str) -> Self {
+        let mut keymap = Self::default();
+        for line in contents.lines() {
+            let Some((key, value)) = line.trim().split_once('=') else {
+                continue;
+            };
+            if key == "version" {
+                if !value
+                    .parse()
+                    .is_ok_and(|version: u32| version <= Self::VERSION)
+                {
+                    return Self::default();
+                }
+            } else if key == "preset" {
+                if let Some(preset) = Preset::ALL.iter().find(|preset| preset.name() == value) {
+                    keymap.set_preset(*preset);
+                }
+            } else if key == "das" || key == "arr" {
+                if let Ok(millis) = value.parse::<u64>() {
+                    let millis = millis.min(Self::MAX_MILLIS);
+                    if key == "das" {
+                        keymap.das = millis;
+                    } else {
+                        keymap.arr = millis;
+                    }
+                }
+            } else if let Ok(action) = key.parse::<Action>() {
+                let keys: Vec<KeyCode> = value.split(',').filter_map(parse_key).collect();
+                if !keys.is_empty() {
+                    keymap.keys[action.index()] = keys;
+                }
+            }
+        }
+        keymap
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut contents = format!("version={}\n", Self::VERSION);
+        for action in Action::ALL {
+            let keys: Vec<String> = self.keys(action).iter().filter_map(key_name).collect();
+            contents.push_str(&format!("{}={}\n", action.name(), keys.join(",")));
+        }
+        contents.push_str(&format!("das={}\narr={}\n", self.das, self.arr));
+        contents
+    }
+
+    pub fn action(&self, code: KeyCode) -> Option<Action> {
+        let code = match code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        Action::ALL
+            .into_iter()
+            .find(|action| self.keys(*action).contains(&code))
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        &self.keys[action.index()]
+    }
+
+    // Makes the key the only one of the action, taking it away from any other.
+    // Returns whether the key could be bound at all: it can't be the last key
+    // of another action, which would be left out of the game, or with no way
+    // out of it for Pause and Quit.
+    pub fn bind(&mut self, action: Action, code: KeyCode) -> bool {
+        let code = match code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        if key_name(&code).is_none() {
+            return false;
+        }
+        let last_key = |other: &Action| *other != action && self.keys(*other) == [code];
+        if Action::ALL.iter().any(last_key) {
+            return false;
+        }
+        for keys in self.keys.iter_mut() {
+            keys.retain(|key| *key != code);
+        }
+        self.keys[action.index()] = vec![code];
+        true
+    }
+
+    // The preset whose keys the keymap has, untouched.
+    pub fn preset(&self) -> Option<Preset> {
+        Preset::ALL
+            .into_iter()
+            .find(|preset| preset.keymap().keys == self.keys)
+    }
+
+    // Takes the keys of the preset, keeping the repeat delays.
+    pub fn set_preset(&mut self, preset: Preset) {
+        self.keys = preset.keymap().keys;
+    }
+}
+
+pub fn key_name(code: &KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Left => "Left",
+        KeyCode::Right => "Right",
+        KeyCode::Up => "Up",
+        KeyCode::Down => "Down",
+        KeyCode::Enter => "Enter",
+        KeyCode::Esc => "Esc",
+        KeyCode::Backspace => "Backspace",
+        KeyCode::Tab => "Tab",
+        KeyCode::Char(' ') => "Space",
+        KeyCode::Char(c) if c.is_ascii_graphic() && *c != ',' && *c != '=' => {
+            return Some(c.to_string())
+        }
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let code = match name.trim() {
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Enter" => KeyCode::Enter,
+        "Esc" => KeyCode::Esc,
+        "Backspace" => KeyCode::Backspace,
+        "Tab" => KeyCode::Tab,
+        "Space" => KeyCode::Char(' '),
+        name => {
+            let mut chars = name.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c.to_ascii_lowercase()),
+                _ => return None,
+            }
+        }
+    };
+    key_name(&code).map(|_| code)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.action(KeyCode::Left), Some(Action::MoveLeft));
+        assert_eq!(keymap.action(KeyCode::Enter), Some(Action::Cycle));
+        assert_eq!(keymap.action(KeyCode::Char('h')), None);
+        assert_eq!(keymap.preset(), Some(Preset::Arrows));
+
+        let keymap = Preset::Vim.keymap();
+        assert_eq!(keymap.action(KeyCode::Char('h')), Some(Action::MoveLeft));
+        assert_eq!(keymap.action(KeyCode::Char('K')), Some(Action::HardDrop));
+        assert_eq!(keymap.action(KeyCode::Left), None);
+
+        let keymap = Preset::Wasd.keymap();
+        assert_eq!(keymap.action(KeyCode::Char('s')), Some(Action::SoftDrop));
+        assert_eq!(keymap.action(KeyCode::Tab), Some(Action::Hold));
+    }
+
+    #[test]
+    fn test_bind() {
+        let mut keymap = Keymap::default();
+        assert!(keymap.bind(Action::Pause, KeyCode::Enter));
+        assert_eq!(keymap.keys(Action::Pause), &[KeyCode::Enter]);
+        assert_eq!(keymap.keys(Action::Cycle), &[KeyCode::Char(' ')]);
+        assert_eq!(keymap.action(KeyCode::Char('p')), None);
+        assert_eq!(keymap.preset(), None);
+
+        assert!(!keymap.bind(Action::Pause, KeyCode::F(1)));
+        assert_eq!(keymap.keys(Action::Pause), &[KeyCode::Enter]);
+    }
+
+    #[test]
+    fn test_bind_last_key() {
+        let mut keymap = Keymap::default();
+        assert!(!keymap.bind(Action::MoveLeft, KeyCode::Esc));
+        assert!(!keymap.bind(Action::Cycle, KeyCode::Char('p')));
+        assert_eq!(keymap, Keymap::default());
+
+        // once it has another key, the first one can go
+        assert!(keymap.bind(Action::Quit, KeyCode::Char('q')));
+        assert!(keymap.bind(Action::MoveLeft, KeyCode::Esc));
+        assert_eq!(keymap.keys(Action::Quit), &[KeyCode::Char('q')]);
+    }
+
+    #[test]
+    fn test_serialize_and_parse() {
+        let mut keymap = Preset::Wasd.keymap();
+        keymap.bind(Action::Quit, KeyCode::Char('q'));
+        keymap.das = 120;
+        keymap.arr = 0;
+
+        assert_eq!(Keymap::parse(&keymap.serialize()), keymap);
+    }
+
+    #[test]
+    fn test_serialize_and_parse_stolen_key() {
+        let mut keymap = Keymap::default();
+        // Left is all MoveLeft has
+        assert!(!keymap.bind(Action::Cycle, KeyCode::Left));
+        assert!(keymap.bind(Action::MoveLeft, KeyCode::Char('j')));
+        assert!(keymap.bind(Action::Cycle, KeyCode::Left));
+
+        let parsed = Keymap::parse(&keymap.serialize());
+        assert_eq!(parsed, keymap);
+        assert_eq!(parsed.action(KeyCode::Left), Some(Action::Cycle));
+        assert_eq!(parsed.action(KeyCode::Char('j')), Some(Action::MoveLeft));
+    }
+
+    #[test]
+    fn test_parse() {
+        let keymap = Keymap::parse(
+            "version=1
+            preset=vim
+            quit=q,Esc
+            cycle=
+            fly=Space
+            pause=F1
+            das=5000
+            arr=abc",
+        );
+
+        assert_eq!(keymap.action(KeyCode::Char('h')), Some(Action::MoveLeft));
+        assert_eq!(
+            keymap.keys(Action::Quit),
+            &[KeyCode::Char('q'), KeyCode::Esc]
+        );
+        assert_eq!(
+            keymap.keys(Action::Cycle),
+            Preset::Vim.keymap().keys(Action::Cycle)
+        );
+        assert_eq!(keymap.keys(Action::Pause), &[KeyCode::Char('p')]);
+        assert_eq!(keymap.das, Keymap::MAX_MILLIS);
+        assert_eq!(keymap.arr, Keymap::ARR_MILLIS);
+        assert_eq!(keymap.preset(), None);
+        assert_eq!(Keymap::parse(""), Keymap::default());
+        assert_eq!(Keymap::parse("version=2\npreset=vim"), Keymap::default());
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{
+    pit::PitSize,
+    save::{Persist, Save},
+};
+
+// Milliseconds it takes for a column to fall one row, per level.
+const GRAVITY_MILLIS: [u64; 15] = [
+    1000, 850, 720, 610, 520, 440, 370, 310, 260, 220, 185, 155, 130, 110, 95,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Level {
+    starting: usize,
+    current: usize,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self::new(Self::MIN)
+    }
+}
+
+impl Level {
+    pub const MIN: usize = 1;
+    pub const MAX: usize = GRAVITY_MILLIS.len();
+    pub const JEWELS_PER_LEVEL: usize = 35;
+
+    pub fn new(starting: usize) -> Self {
+        let starting = starting.clamp(Self::MIN, Self::MAX);
+        Self {
+            starting,
+            current: starting,
+        }
+    }
+
+    // Levels up every `JEWELS_PER_LEVEL` jewels cleared, returns whether it did.
+    pub fn update(&mut self, blocks_score: usize) -> bool {
+        let level = (self.starting + blocks_score / Self::JEWELS_PER_LEVEL).min(Self::MAX);
+        let leveled_up = level > self.current;
+        self.current = level;
+        leveled_up
+    }
+
+    #[inline]
+    pub fn number(&self) -> usize {
+        self.current
+    }
+
+    pub fn gravity_millis(&self) -> u64 {
+        GRAVITY_MILLIS[self.current - Self::MIN]
+    }
+}
+
+impl Persist for Level {
+    fn persist(&self, save: &mut Save) {
+        save.set("level.starting", self.starting);
+        save.set("level.current", self.current);
+    }
+
+    fn restore(save: &Save, _size: PitSize) -> Option<Self> {
+        let level = Self {
+            starting: save.get("level.starting")?,
+            current: save.get("level.current")?,
+        };
+        let range = Self::MIN..=Self::MAX;
+        (range.contains(&level.starting) && range.contains(&level.current)).then_some(level)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_update() {
+        let mut level = Level::default();
+        assert_eq!(level.number(), 1);
+        assert!(!level.update(Level::JEWELS_PER_LEVEL - 1));
+        assert_eq!(level.number(), 1);
+        assert!(level.update(Level::JEWELS_PER_LEVEL));
+        assert_eq!(level.number(), 2);
+        assert!(!level.update(Level::JEWELS_PER_LEVEL + 1));
+        assert!(level.update(Level::JEWELS_PER_LEVEL * 1000));
+        assert_eq!(level.number(), Level::MAX);
+    }
+
+    #[test]
+    fn test_starting_level() {
+        let mut level = Level::new(5);
+        assert_eq!(level.number(), 5);
+        level.update(Level::JEWELS_PER_LEVEL);
+        assert_eq!(level.number(), 6);
+
+        assert_eq!(Level::new(0).number(), Level::MIN);
+        assert_eq!(Level::new(100).number(), Level::MAX);
+    }
+
+    #[test]
+    fn test_gravity() {
+        assert_eq!(Level::default().gravity_millis(), 1000);
+        assert!(GRAVITY_MILLIS.windows(2).all(|pair| pair[0] > pair[1]));
+        assert_eq!(Level::new(Level::MAX).gravity_millis(), 95);
+    }
+}
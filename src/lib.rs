@@ -1,16 +1,37 @@
+pub mod audio;
+pub mod auto_repeat;
 pub mod block;
 pub mod board;
+pub mod cli;
 pub mod column;
 pub mod frame;
+pub mod game;
+pub mod game_over;
+pub mod hold;
+pub mod keymap;
+pub mod level;
+pub mod menu;
+pub mod mode;
+pub mod music;
+pub mod overlay;
 pub mod pit;
+pub mod queue;
+pub mod ranking;
 pub mod renderer;
+pub mod replay;
+pub mod rules;
+pub mod save;
+pub mod source;
 pub mod terminal;
 pub mod timer;
+pub mod xdg;
+
+use std::{fmt, str::FromStr};
 
 const NUM_COLS: usize = 6;
 const NUM_ROWS: usize = 13;
 const PIT_STARTING_X: usize = 10;
-const WIDTH: usize = NUM_COLS + PIT_STARTING_X;
+const SIDE_PANEL_WIDTH: usize = 16;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Point {
@@ -24,3 +45,19 @@ macro_rules! point {
         Point { x: $x, y: $y }
     };
 }
+
+// Written in saved games as `x:y`.
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.x, self.y)
+    }
+}
+
+impl FromStr for Point {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (x, y) = s.split_once(':').ok_or(())?;
+        Ok(point!(x.parse().or(Err(()))?, y.parse().or(Err(()))?))
+    }
+}
@@ -1,109 +1,401 @@
 use crossterm::{
-    event::{self, poll, Event, KeyCode},
+    event::{self, poll, Event, KeyCode, KeyEventKind},
     Result,
 };
 use rust_columns::{
-    board::Board,
-    column::Column,
-    frame::{new_frame, Drawable, Frame},
-    pit::Pit,
-    renderer, terminal,
+    audio::{self, Audio, Sfx, SAMPLE_RATE},
+    auto_repeat::AutoRepeat,
+    cli::Args,
+    frame::{new_frame, Drawable},
+    game::{Game, GameEvent},
+    game_over::{GameOver, GameOverChoice},
+    keymap::{Action, Keymap},
+    menu::{Menu, MenuAction},
+    music::Music,
+    overlay::Overlay,
+    pit::{PitEvent, PitSize},
+    ranking::Ranking,
+    renderer::{self, Layout, Message},
+    replay::{Playback, Replay},
+    rules::Rules,
+    save::Save,
+    terminal,
 };
 use std::{
-    io,
+    env, fs,
+    io::{self, Write},
+    path::Path,
     sync::mpsc,
     thread,
     time::{Duration, Instant},
 };
 
+enum Screen {
+    Menu(Menu),
+    Playing(Box<Game>, bool),
+    Replay(Box<Game>, Playback),
+    GameOver(GameOver, Rules),
+}
+
+// The sound of what happened in the game, if any.
+fn sfx(event: &GameEvent) -> Option<Sfx> {
+    match event {
+        GameEvent::Moved => Some(Sfx::Move),
+        GameEvent::Cycled => Some(Sfx::Rotate),
+        GameEvent::Held => Some(Sfx::Hold),
+        GameEvent::Pit(PitEvent::ColumnLanded { .. }) => Some(Sfx::Land),
+        // a single sound for every match, the deeper the chain the higher
+        GameEvent::Pit(PitEvent::CascadeStep { depth: 1 }) => Some(Sfx::Match),
+        GameEvent::Pit(PitEvent::CascadeStep { depth }) => Some(Sfx::Chain(*depth)),
+        GameEvent::Pit(_) => None,
+        GameEvent::LevelUp(_) => Some(Sfx::LevelUp),
+        GameEvent::GameOver => Some(Sfx::GameOver),
+    }
+}
+
 fn main() -> Result<()> {
-    renderer::assert_screen_size().expect("Failed when asserting the screen size requirements");
+    let args = Args::parse(env::args().skip(1)).expect("Failed parsing the command line arguments");
+    // no game at all, just the music written to a file
+    if let Some(path) = &args.render_music {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        audio::write_wav(&mut file, SAMPLE_RATE, &Music::render_all(2, SAMPLE_RATE))?;
+        file.flush()?;
+        println!("Music rendered to {}", path.display());
+        return Ok(());
+    }
+    // a replay is played with the rules it was recorded with
+    let playback = args
+        .replay
+        .as_ref()
+        .map(|path| Playback::new(Replay::load(path).expect("Failed loading the replay")));
+    // the size of the pit is settled for the whole session
+    let size = playback
+        .as_ref()
+        .map_or(args.pit, |playback| playback.replay().rules.pit);
+    // as large as the terminal allows, text and board included
+    let layout = renderer::assert_screen_size(&size)
+        .expect("Failed when asserting the screen size requirements");
     // Drop guard for terminal setup and cleanup
-    let mut _t = terminal::TerminalGuard::create();
+    let terminal_guard = terminal::TerminalGuard::create();
     // Render loop in a separate thread
-    let (render_tx, render_rx) = mpsc::channel::<Frame>();
+    let (render_tx, render_rx) = mpsc::channel::<Message>();
     let render_handle = thread::spawn(move || -> Result<()> {
         let mut stdout = io::stdout();
-        let mut last_frame = new_frame();
-        renderer::init(&mut stdout)?;
-        while let Ok(curr_frame) = render_rx.recv() {
-            renderer::render(&mut stdout, &last_frame, &curr_frame)?;
-            last_frame = curr_frame;
+        let mut layout = Some(layout);
+        let mut last_frame = new_frame(size);
+        renderer::init(&mut stdout, &size, layout.unwrap())?;
+        while let Ok(message) = render_rx.recv() {
+            match message {
+                Message::Resize(cols, rows) => {
+                    layout = Layout::fit(&size, cols as usize, rows as usize);
+                    match layout {
+                        Some(layout) => renderer::init(&mut stdout, &size, layout)?,
+                        None => renderer::too_small(&mut stdout, &size)?,
+                    }
+                    // the screen was wiped, so the next frame is drawn in full
+                    last_frame = new_frame(size);
+                }
+                Message::Draw(curr_frame) => {
+                    if let Some(layout) = layout {
+                        renderer::render(&mut stdout, &last_frame, &curr_frame, layout)?;
+                        last_frame = curr_frame;
+                    }
+                }
+            }
         }
         Ok(())
     });
 
     let fps_duration = Duration::from_nanos(1_000_000_000 / 60); // 60 fps duration ~16ms
     let mut instant = Instant::now();
-    let mut board = Board::default();
-    let mut pit = Pit::default();
-    let mut column = Column::new();
-    let mut upcoming_column = Column::new();
-    upcoming_column.stand_by = true;
+    // the seed given on the command line is only used for the first game
+    let mut seed = args.seed;
+    let mut last_seed = None;
+    let mut ranking = Ranking::load();
+    let mut keymap = Keymap::load();
+    let mut auto_repeat = AutoRepeat::new(keymap.das, keymap.arr);
+    let record = args.record.clone().or_else(Replay::path);
+    let save_path = Save::path();
+    let mut screen = match playback {
+        Some(playback) => {
+            let Replay { seed, rules, .. } = *playback.replay();
+            let game = Game::new(Some(seed), rules, ranking.best());
+            Screen::Replay(Box::new(game), playback)
+        }
+        None => {
+            let mut menu = Menu::new(args.rules(), ranking.clone(), keymap.clone());
+            menu.set_resumable(saved_game(save_path.as_deref(), size).is_some());
+            Screen::Menu(menu)
+        }
+    };
+    let mut too_small = false;
+    let mut audio = Audio::new(args.audio.open());
 
     'gameloop: loop {
         let delta = instant.elapsed();
         instant = Instant::now();
-        let mut curr_frame = new_frame();
+        let mut curr_frame = new_frame(size);
+        audio.advance(delta);
+        // actions of the player, performed all at once in the next step
+        let mut inputs = Vec::new();
 
         while poll(Duration::default())? {
-            if let Event::Key(key_event) = event::read()? {
-                match key_event.code {
-                    KeyCode::Esc => {
-                        break 'gameloop;
+            let event = event::read()?;
+            if let Event::Resize(cols, rows) = event {
+                too_small = Layout::fit(&size, cols as usize, rows as usize).is_none();
+                render_tx
+                    .send(Message::Resize(cols, rows))
+                    .expect("Failed sending the new size to the render thread");
+                continue;
+            }
+            // releases stop the held keys, and in a game their repeats are for
+            // us to make, when the terminal tells them apart: only moves and
+            // soft drops repeat, nobody means to hard drop every new column
+            if let Event::Key(key_event) = event {
+                let action = keymap.action(key_event.code);
+                let playing = matches!(screen, Screen::Playing(..));
+                match key_event.kind {
+                    KeyEventKind::Release => {
+                        if let Some(action) = action {
+                            auto_repeat.release(action);
+                        }
+                        continue;
                     }
-                    KeyCode::Left => {
-                        column.move_left(&pit.heap);
+                    KeyEventKind::Repeat if playing => continue,
+                    _ => {}
+                }
+            }
+            match &mut screen {
+                Screen::Menu(menu) => {
+                    if let Event::Key(key_event) = event {
+                        match menu.handle(key_event.code) {
+                            MenuAction::Continue => {
+                                let game = saved_game(save_path.as_deref(), size)
+                                    .and_then(|save| Game::restore(&save, ranking.best()));
+                                if let (Some(game), Some(path)) = (game, &save_path) {
+                                    // a game is resumed only once
+                                    Save::remove(path).ok();
+                                    last_seed = Some(game.seed());
+                                    // paused, to give the player a moment to look
+                                    screen = Screen::Playing(Box::new(game), true);
+                                } else {
+                                    menu.set_resumable(false);
+                                }
+                            }
+                            MenuAction::Play(rules) => {
+                                let game = Game::new(seed.take(), rules, ranking.best());
+                                last_seed = Some(game.seed());
+                                screen = Screen::Playing(Box::new(game), false);
+                            }
+                            MenuAction::Rebind(rebound) => {
+                                keymap = rebound;
+                                auto_repeat = AutoRepeat::new(keymap.das, keymap.arr);
+                                // a keymap that can't be saved still works for this session
+                                keymap.save().ok();
+                            }
+                            MenuAction::Quit => {
+                                break 'gameloop;
+                            }
+                            MenuAction::None => {}
+                        }
                     }
-                    KeyCode::Right => {
-                        column.move_right(&pit.heap);
+                }
+                Screen::Playing(game, paused) => match event {
+                    // do not let the columns fall while the player is away
+                    Event::FocusLost => {
+                        *paused = true;
                     }
-                    KeyCode::Down => {
-                        column.move_down(&pit.heap);
+                    Event::Key(key_event) => match keymap.action(key_event.code) {
+                        Some(Action::Quit) => {
+                            auto_repeat.clear();
+                            save_replay(game, record.as_deref());
+                            // put the game aside, back to the title screen
+                            let saved = save_path
+                                .as_deref()
+                                .is_some_and(|path| game.save().store(path).is_ok());
+                            let mut menu = Menu::new(game.rules(), ranking.clone(), keymap.clone());
+                            menu.set_resumable(saved);
+                            screen = Screen::Menu(menu);
+                        }
+                        Some(Action::Pause) => {
+                            *paused = !*paused;
+                        }
+                        _ if *paused => {}
+                        Some(action) => {
+                            if terminal_guard.enhanced() {
+                                auto_repeat.press(action);
+                            }
+                            inputs.push(action);
+                        }
+                        None => {}
+                    },
+                    _ => {}
+                },
+                Screen::Replay(_, playback) => {
+                    if let Event::Key(key_event) = event {
+                        match key_event.code {
+                            KeyCode::Esc => {
+                                break 'gameloop;
+                            }
+                            KeyCode::Left => playback.slower(),
+                            KeyCode::Right => playback.faster(),
+                            KeyCode::Char(' ') | KeyCode::Char('p') => {
+                                playback.paused = !playback.paused;
+                            }
+                            _ => {}
+                        }
                     }
-                    KeyCode::Char(' ') | KeyCode::Enter => {
-                        column.cycle();
+                }
+                Screen::GameOver(game_over, rules) => {
+                    if let Event::Key(key_event) = event {
+                        match key_event.code {
+                            // skipping the ranking takes a choice of its own
+                            KeyCode::Esc if game_over.prompting() => {
+                                game_over.cancel_initials();
+                            }
+                            KeyCode::Esc => {
+                                let mut menu = Menu::new(*rules, ranking.clone(), keymap.clone());
+                                menu.set_resumable(
+                                    saved_game(save_path.as_deref(), size).is_some(),
+                                );
+                                screen = Screen::Menu(menu);
+                            }
+                            KeyCode::Char(c) if game_over.prompting() => {
+                                game_over.type_initial(c);
+                            }
+                            KeyCode::Backspace => {
+                                game_over.erase_initial();
+                            }
+                            KeyCode::Enter if game_over.prompting() => {
+                                if let Some(entry) = game_over.confirm_initials() {
+                                    ranking.insert(entry);
+                                    // a ranking that can't be saved is no reason to stop playing
+                                    ranking.save().ok();
+                                }
+                            }
+                            _ if game_over.prompting() => {}
+                            KeyCode::Up | KeyCode::Down => {
+                                game_over.select_next();
+                            }
+                            KeyCode::Char(' ') | KeyCode::Enter => match game_over.choice() {
+                                GameOverChoice::PlayAgain => {
+                                    // same rules, but a brand new sequence of columns
+                                    let game = Game::new(None, *rules, ranking.best());
+                                    last_seed = Some(game.seed());
+                                    screen = Screen::Playing(Box::new(game), false);
+                                }
+                                GameOverChoice::Quit => {
+                                    break 'gameloop;
+                                }
+                            },
+                            _ => {}
+                        }
                     }
-                    _ => {}
                 }
             }
         }
 
-        let (score, blocks_score) = pit.update(&mut column, delta);
-        // move column down if dropping, otherwise create a new one
-        if pit.stable() {
-            let dropping = column.update(&pit.heap, delta);
-            // if the column landed already, renew it
-            if !dropping {
-                column = upcoming_column;
-                column.stand_by = false;
-                upcoming_column = Column::new();
-                upcoming_column.stand_by = true;
+        match &mut screen {
+            Screen::Menu(menu) => {
+                audio.stop_music();
+                menu.draw(&mut curr_frame);
+            }
+            Screen::Playing(game, paused) => {
+                // nobody can play what they can't see
+                if too_small {
+                    *paused = true;
+                }
+                // the game only goes on when stepped, pausing freezes it all
+                if *paused {
+                    audio.stop_music();
+                    // the keys might be released while nobody is listening
+                    auto_repeat.clear();
+                    // hide the pit, so that nobody plans ahead while paused
+                    game.board().draw(&mut curr_frame);
+                    Overlay::new(size, &["PAUSED"]).draw(&mut curr_frame);
+                } else {
+                    inputs.extend(auto_repeat.update(delta));
+                    let events = game.step(&inputs, delta);
+                    events
+                        .iter()
+                        .filter_map(sfx)
+                        .for_each(|sfx| audio.play(sfx));
+                    audio.play_music(game.level(), game.pit().danger());
+                    game.draw(&mut curr_frame);
+                }
+
+                if game.over() {
+                    let pit = game.pit();
+                    let mut game_over = GameOver::new(
+                        pit.size(),
+                        pit.score(),
+                        pit.blocks_score(),
+                        game.level(),
+                        pit.max_chain(),
+                        game.seed(),
+                    );
+                    if ranking.qualifies(game_over.score()) {
+                        game_over.prompt_initials();
+                    }
+                    save_replay(game, record.as_deref());
+                    screen = Screen::GameOver(game_over, game.rules());
+                }
+            }
+            Screen::Replay(game, playback) => {
+                let over = game.over() || playback.finished(game.ticks());
+                if over || playback.paused || too_small {
+                    audio.stop_music();
+                } else {
+                    let events = playback.step(game, delta);
+                    events
+                        .iter()
+                        .filter_map(sfx)
+                        .for_each(|sfx| audio.play(sfx));
+                    audio.play_music(game.level(), game.pit().danger());
+                }
+                game.draw(&mut curr_frame);
+                playback.draw(&mut curr_frame);
+                if over {
+                    Overlay::new(size, &["REPLAY", "OVER", "", "Esc to", "quit"])
+                        .draw(&mut curr_frame);
+                }
+            }
+            Screen::GameOver(game_over, _) => {
+                audio.stop_music();
+                game_over.draw(&mut curr_frame);
             }
         }
-        // keep track of scores, etc. in the board
-        board.update(score, blocks_score);
-        // draw elements on the current frame
-        board.draw(&mut curr_frame);
-        pit.draw(&mut curr_frame);
-        column.draw(&mut curr_frame);
-        upcoming_column.draw(&mut curr_frame);
         // render
         render_tx
-            .send(curr_frame)
+            .send(Message::Draw(curr_frame))
             .expect("Failed sending curr_frame to the render thread");
 
-        if pit.topped_up() {
-            // lose game
-            break;
-        }
-
         thread::sleep(fps_duration.saturating_sub(instant.elapsed()));
     }
 
     // Hygene
     drop(render_tx);
     render_handle.join().unwrap()?;
+    drop(terminal_guard);
+    audio.finish().expect("Failed writing the audio");
+    // the seed is all it takes to replay the same sequence of columns
+    if let Some(seed) = last_seed {
+        println!("Seed of the last game: {seed}");
+    }
 
     Ok(())
 }
+
+// The game put aside last time, if it can be played in a pit of this size.
+fn saved_game(path: Option<&Path>, size: PitSize) -> Option<Save> {
+    let save = Save::load(path?)?;
+    (save.get::<PitSize>("rules.pit")? == size).then_some(save)
+}
+
+// Keeps the game for later, unless there's nowhere to keep it.
+fn save_replay(game: &Game, path: Option<&Path>) {
+    if let Some(path) = path {
+        // losing the replay is no reason to stop playing
+        game.replay().save(path).ok();
+    }
+}
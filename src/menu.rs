@@ -0,0 +1,499 @@
+use crate::{
+    frame::{draw_text, Drawable, Frame, Pixel},
+    keymap::{key_name, Action, Keymap, Preset},
+    level::Level,
+    mode::Mode,
+    queue::Queue,
+    ranking::Ranking,
+    rules::Rules,
+};
+use crossterm::{event::KeyCode, style::Color};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MenuPage {
+    Main,
+    Options,
+    HighScores,
+    Controls,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MenuItem {
+    Continue,
+    Play,
+    Mode,
+    Level,
+    Options,
+    HighScores,
+    Quit,
+    Magic,
+    Ghost,
+    Next,
+    Hold,
+    Controls,
+    Keys,
+    Bind(Action),
+    Delay,
+    Repeat,
+    Back,
+}
+
+impl MenuItem {
+    const MAIN: [Self; 6] = [
+        Self::Play,
+        Self::Mode,
+        Self::Level,
+        Self::Options,
+        Self::HighScores,
+        Self::Quit,
+    ];
+    // with a game put aside to come back to
+    const RESUME: [Self; 7] = [
+        Self::Continue,
+        Self::Play,
+        Self::Mode,
+        Self::Level,
+        Self::Options,
+        Self::HighScores,
+        Self::Quit,
+    ];
+    const OPTIONS: [Self; 6] = [
+        Self::Magic,
+        Self::Ghost,
+        Self::Next,
+        Self::Hold,
+        Self::Controls,
+        Self::Back,
+    ];
+    const HIGH_SCORES: [Self; 1] = [Self::Back];
+    const CONTROLS: [Self; 12] = [
+        Self::Keys,
+        Self::Bind(Action::MoveLeft),
+        Self::Bind(Action::MoveRight),
+        Self::Bind(Action::SoftDrop),
+        Self::Bind(Action::HardDrop),
+        Self::Bind(Action::Cycle),
+        Self::Bind(Action::Hold),
+        Self::Bind(Action::Pause),
+        Self::Bind(Action::Quit),
+        Self::Delay,
+        Self::Repeat,
+        Self::Back,
+    ];
+}
+
+// What the menu asks the game loop to do after a key press.
+#[derive(Debug, PartialEq)]
+pub enum MenuAction {
+    None,
+    Continue,
+    Play(Rules),
+    Rebind(Keymap),
+    Quit,
+}
+
+// Title screen, where the rules of the next game are chosen.
+pub struct Menu {
+    page: MenuPage,
+    selected: usize,
+    rules: Rules,
+    ranking: Ranking,
+    keymap: Keymap,
+    // a saved game is there to be continued
+    resumable: bool,
+    // the action waiting for its new key
+    binding: Option<Action>,
+}
+
+impl Menu {
+    pub fn new(rules: Rules, ranking: Ranking, keymap: Keymap) -> Self {
+        Self {
+            page: MenuPage::Main,
+            selected: 0,
+            rules,
+            ranking,
+            keymap,
+            resumable: false,
+            binding: None,
+        }
+    }
+
+    pub fn set_resumable(&mut self, resumable: bool) {
+        self.resumable = resumable;
+    }
+
+    fn items(&self) -> &'static [MenuItem] {
+        match self.page {
+            MenuPage::Main if self.resumable => &MenuItem::RESUME,
+            MenuPage::Main => &MenuItem::MAIN,
+            MenuPage::Options => &MenuItem::OPTIONS,
+            MenuPage::HighScores => &MenuItem::HIGH_SCORES,
+            MenuPage::Controls => &MenuItem::CONTROLS,
+        }
+    }
+
+    fn open(&mut self, page: MenuPage) {
+        self.page = page;
+        self.selected = 0;
+    }
+
+    pub fn handle(&mut self, code: KeyCode) -> MenuAction {
+        if let Some(action) = self.binding.take() {
+            // Esc gives up, keys that can't be bound are ignored
+            if code == KeyCode::Esc {
+                return MenuAction::None;
+            }
+            if !self.keymap.bind(action, code) {
+                self.binding = Some(action);
+                return MenuAction::None;
+            }
+            return MenuAction::Rebind(self.keymap.clone());
+        }
+        let keymap = self.keymap.clone();
+        let items = self.items();
+        match code {
+            KeyCode::Up => {
+                self.selected = (self.selected + items.len() - 1) % items.len();
+            }
+            KeyCode::Down => {
+                self.selected = (self.selected + 1) % items.len();
+            }
+            KeyCode::Left => self.change(items[self.selected], false),
+            KeyCode::Right => self.change(items[self.selected], true),
+            KeyCode::Char(' ') | KeyCode::Enter => match items[self.selected] {
+                MenuItem::Continue => return MenuAction::Continue,
+                MenuItem::Play => return MenuAction::Play(self.rules),
+                MenuItem::Options => self.open(MenuPage::Options),
+                MenuItem::HighScores => self.open(MenuPage::HighScores),
+                MenuItem::Controls => self.open(MenuPage::Controls),
+                MenuItem::Bind(action) => self.binding = Some(action),
+                MenuItem::Quit => return MenuAction::Quit,
+                MenuItem::Back => self.back(),
+                item => self.change(item, true),
+            },
+            KeyCode::Esc => {
+                if self.page == MenuPage::Main {
+                    return MenuAction::Quit;
+                }
+                self.back();
+            }
+            _ => {}
+        }
+        if self.keymap != keymap {
+            return MenuAction::Rebind(self.keymap.clone());
+        }
+        MenuAction::None
+    }
+
+    fn back(&mut self) {
+        match self.page {
+            MenuPage::Controls => {
+                self.open(MenuPage::Options);
+                self.selected = 4;
+            }
+            _ => self.open(MenuPage::Main),
+        }
+    }
+
+    // Steps the value of the item forwards or backwards.
+    fn change(&mut self, item: MenuItem, forwards: bool) {
+        match item {
+            MenuItem::Mode => {
+                self.rules.mode = match self.rules.mode {
+                    Mode::Easy => Mode::Arcade,
+                    Mode::Arcade => Mode::Easy,
+                };
+            }
+            MenuItem::Level => {
+                self.rules.level = if forwards {
+                    (self.rules.level + 1).min(Level::MAX)
+                } else {
+                    self.rules.level.saturating_sub(1).max(Level::MIN)
+                };
+            }
+            MenuItem::Magic => {
+                self.rules.magic = !self.rules.magic;
+            }
+            MenuItem::Ghost => {
+                self.rules.ghost = !self.rules.ghost;
+            }
+            MenuItem::Hold => {
+                self.rules.hold = !self.rules.hold;
+            }
+            MenuItem::Next => {
+                // Enter goes round, back to a single one after the last
+                self.rules.next = match (forwards, self.rules.next) {
+                    (true, Queue::MAX_LEN) => Queue::MIN_LEN,
+                    (true, next) => next + 1,
+                    (false, next) => next.saturating_sub(1).max(Queue::MIN_LEN),
+                };
+            }
+            MenuItem::Keys => {
+                // a custom keymap is left for the first preset
+                let len = Preset::ALL.len();
+                let next = match self.keymap.preset() {
+                    Some(preset) => {
+                        let i = Preset::ALL.iter().position(|p| *p == preset).unwrap();
+                        if forwards {
+                            (i + 1) % len
+                        } else {
+                            (i + len - 1) % len
+                        }
+                    }
+                    None => 0,
+                };
+                self.keymap.set_preset(Preset::ALL[next]);
+            }
+            MenuItem::Delay => {
+                self.keymap.das = step_millis(self.keymap.das, forwards);
+            }
+            MenuItem::Repeat => {
+                self.keymap.arr = step_millis(self.keymap.arr, forwards);
+            }
+            _ => {}
+        }
+    }
+
+    fn label(&self, item: MenuItem) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        match item {
+            MenuItem::Continue => "Continue".to_string(),
+            MenuItem::Play => "Play".to_string(),
+            MenuItem::Mode => format!("Mode   {}", self.rules.mode.name()),
+            MenuItem::Level => format!("Level  {}", self.rules.level),
+            MenuItem::Options => "Options".to_string(),
+            MenuItem::HighScores => "High scores".to_string(),
+            MenuItem::Quit => "Quit".to_string(),
+            MenuItem::Magic => format!("Magic  {}", on_off(self.rules.magic)),
+            MenuItem::Ghost => format!("Ghost  {}", on_off(self.rules.ghost)),
+            MenuItem::Next => format!("Next   {}", self.rules.next),
+            MenuItem::Hold => format!("Hold   {}", on_off(self.rules.hold)),
+            MenuItem::Controls => "Controls".to_string(),
+            MenuItem::Keys => format!(
+                "Keys   {}",
+                self.keymap
+                    .preset()
+                    .map_or("custom", |preset| preset.name())
+            ),
+            MenuItem::Bind(action) if self.binding == Some(action) => {
+                format!("{:<10}press a key", action.label())
+            }
+            MenuItem::Bind(action) => {
+                let keys: Vec<String> = self
+                    .keymap
+                    .keys(action)
+                    .iter()
+                    .filter_map(key_name)
+                    .collect();
+                format!("{:<10}{}", action.label(), keys.join("/"))
+            }
+            MenuItem::Delay => format!("{:<10}{}ms", "Delay", self.keymap.das),
+            MenuItem::Repeat => format!("{:<10}{}ms", "Repeat", self.keymap.arr),
+            MenuItem::Back => "Back".to_string(),
+        }
+    }
+
+    fn draw_ranking(&self, frame: &mut Frame) {
+        if self.ranking.entries().is_empty() {
+            draw_text(frame, 1, 2, "No scores yet", Color::White);
+            return;
+        }
+        draw_text(frame, 1, 1, "   NAM  SCORE JWL LV DATE", Color::Grey);
+        for (i, entry) in self.ranking.entries().iter().enumerate() {
+            let row = format!(
+                "{:>2} {:<3} {:>6} {:>3} {:>2} {}",
+                i + 1,
+                entry.initials,
+                entry.score,
+                entry.jewels,
+                entry.level,
+                entry.date
+            );
+            draw_text(frame, 1, 2 + i, &row, Color::White);
+        }
+    }
+}
+
+// Auto-repeat delays go up and down 10 millis at a time.
+fn step_millis(millis: u64, forwards: bool) -> u64 {
+    if forwards {
+        (millis + 10).min(Keymap::MAX_MILLIS)
+    } else {
+        millis.saturating_sub(10)
+    }
+}
+
+impl Drawable for Menu {
+    fn draw(&self, frame: &mut Frame) {
+        for col in frame.iter_mut() {
+            col.fill(Pixel::default());
+        }
+        // long pages take up every row, but the one of the title
+        let (title, title_row, top) = match self.page {
+            MenuPage::Main => ("RUST COLUMNS", 1, 3),
+            MenuPage::Options => ("OPTIONS", 1, 3),
+            MenuPage::Controls => ("CONTROLS", 0, 1),
+            MenuPage::HighScores => ("HIGH SCORES", 0, self.rules.pit.rows - 1),
+        };
+        draw_text(frame, 2, title_row, title, Color::AnsiValue(226));
+        if self.page == MenuPage::HighScores {
+            self.draw_ranking(frame);
+        }
+
+        for (i, item) in self.items().iter().enumerate() {
+            let (marker, color) = if i == self.selected {
+                ('>', Color::AnsiValue(226))
+            } else {
+                (' ', Color::White)
+            };
+            let label = format!("{marker} {}", self.label(*item));
+            draw_text(frame, 1, top + i, &label, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_play() {
+        let mut menu = Menu::new(Rules::default(), Ranking::default(), Keymap::default());
+        assert_eq!(
+            menu.handle(KeyCode::Enter),
+            MenuAction::Play(Rules::default())
+        );
+    }
+
+    #[test]
+    fn test_change_rules() {
+        let mut menu = Menu::new(Rules::default(), Ranking::default(), Keymap::default());
+        menu.handle(KeyCode::Down);
+        menu.handle(KeyCode::Right);
+        menu.handle(KeyCode::Down);
+        menu.handle(KeyCode::Left);
+        menu.handle(KeyCode::Right);
+        menu.handle(KeyCode::Right);
+        // back to the top
+        menu.handle(KeyCode::Up);
+        menu.handle(KeyCode::Up);
+
+        assert_eq!(
+            menu.handle(KeyCode::Enter),
+            MenuAction::Play(Rules {
+                mode: Mode::Arcade,
+                level: Level::MIN + 2,
+                ..Rules::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let mut menu = Menu::new(Rules::default(), Ranking::default(), Keymap::default());
+        menu.handle(KeyCode::Down);
+        menu.handle(KeyCode::Down);
+        menu.handle(KeyCode::Down);
+        assert_eq!(menu.handle(KeyCode::Enter), MenuAction::None);
+        assert_eq!(menu.page, MenuPage::Options);
+        menu.handle(KeyCode::Enter);
+        assert!(!menu.rules.magic);
+        menu.handle(KeyCode::Esc);
+        assert_eq!(menu.page, MenuPage::Main);
+        assert_eq!(
+            menu.handle(KeyCode::Enter),
+            MenuAction::Play(Rules {
+                magic: false,
+                ..Rules::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_controls() {
+        let mut menu = Menu::new(Rules::default(), Ranking::default(), Keymap::default());
+        menu.handle(KeyCode::Up);
+        menu.handle(KeyCode::Up);
+        menu.handle(KeyCode::Up);
+        menu.handle(KeyCode::Enter);
+        menu.handle(KeyCode::Down);
+        menu.handle(KeyCode::Enter);
+        assert!(!menu.rules.ghost);
+        menu.handle(KeyCode::Down);
+        menu.handle(KeyCode::Right);
+        menu.handle(KeyCode::Right);
+        assert_eq!(menu.rules.next, 3);
+        menu.handle(KeyCode::Down);
+        menu.handle(KeyCode::Enter);
+        assert!(!menu.rules.hold);
+        menu.handle(KeyCode::Down);
+        menu.handle(KeyCode::Enter);
+        assert_eq!(menu.page, MenuPage::Controls);
+
+        assert_eq!(
+            menu.handle(KeyCode::Right),
+            MenuAction::Rebind(Preset::Wasd.keymap())
+        );
+        menu.handle(KeyCode::Down);
+        menu.handle(KeyCode::Enter);
+        assert_eq!(
+            menu.label(MenuItem::Bind(Action::MoveLeft)),
+            "Left      press a key"
+        );
+        // not bindable, still waiting
+        assert_eq!(menu.handle(KeyCode::F(1)), MenuAction::None);
+        let MenuAction::Rebind(keymap) = menu.handle(KeyCode::Char('j')) else {
+            panic!("expected the keymap to change");
+        };
+        assert_eq!(keymap.action(KeyCode::Char('j')), Some(Action::MoveLeft));
+        assert_eq!(menu.label(MenuItem::Keys), "Keys   custom");
+
+        menu.handle(KeyCode::Esc);
+        assert_eq!(menu.page, MenuPage::Options);
+    }
+
+    #[test]
+    fn test_high_scores() {
+        let mut ranking = Ranking::default();
+        ranking.insert(crate::ranking::Entry {
+            initials: "ABC".to_string(),
+            score: 1234,
+            jewels: 56,
+            level: 2,
+            date: "2023-03-13".to_string(),
+        });
+        let mut menu = Menu::new(Rules::default(), ranking, Keymap::default());
+        menu.handle(KeyCode::Up);
+        menu.handle(KeyCode::Up);
+        menu.handle(KeyCode::Enter);
+        assert_eq!(menu.page, MenuPage::HighScores);
+
+        let mut frame = crate::frame::new_frame(crate::pit::PitSize::default());
+        menu.draw(&mut frame);
+        let line: String = frame.iter().map(|col| col[2].grapheme).collect();
+        assert_eq!(line.trim_end(), "  1 ABC   1234  56  2 2023-03-13");
+
+        menu.handle(KeyCode::Enter);
+        assert_eq!(menu.page, MenuPage::Main);
+    }
+
+    #[test]
+    fn test_quit() {
+        let mut menu = Menu::new(Rules::default(), Ranking::default(), Keymap::default());
+        menu.handle(KeyCode::Up);
+        assert_eq!(menu.handle(KeyCode::Enter), MenuAction::Quit);
+        assert_eq!(menu.handle(KeyCode::Esc), MenuAction::Quit);
+    }
+
+    #[test]
+    fn test_continue() {
+        let mut menu = Menu::new(Rules::default(), Ranking::default(), Keymap::default());
+        menu.set_resumable(true);
+        assert_eq!(menu.handle(KeyCode::Enter), MenuAction::Continue);
+        menu.handle(KeyCode::Down);
+        assert_eq!(
+            menu.handle(KeyCode::Enter),
+            MenuAction::Play(Rules::default())
+        );
+    }
+}
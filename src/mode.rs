@@ -0,0 +1,50 @@
+use std::str::FromStr;
+
+// Game modes differ in how many jewel colors are in play, the more the harder.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Mode {
+    #[default]
+    Easy,
+    Arcade,
+}
+
+impl Mode {
+    pub fn colors(&self) -> usize {
+        match self {
+            Mode::Easy => 4,
+            Mode::Arcade => 6,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Easy => "easy",
+            Mode::Arcade => "arcade",
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Mode::Easy),
+            "arcade" => Ok(Mode::Arcade),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        for mode in [Mode::Easy, Mode::Arcade] {
+            assert_eq!(mode.name().parse(), Ok(mode));
+        }
+        assert_eq!("hard".parse::<Mode>(), Err(()));
+    }
+}
@@ -0,0 +1,317 @@
+use crate::{
+    audio::{mix, note, Tone, Wave},
+    level::Level,
+};
+
+// A looping piece for three voices, written down as in a tracker: one token
+// per step, either a note like `C#4`, a drum hit `x` or a rest `.`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    pub bpm: u32,
+    lead: &'static str,
+    bass: &'static str,
+    drums: &'static str,
+}
+
+pub const THEMES: [Theme; 3] = [
+    Theme {
+        name: "Clotho",
+        bpm: 120,
+        lead: "A4 . C5 E5 D5 . C5 B4 A4 . E4 . A4 B4 C5 . \
+               D5 . F5 A5 G5 . F5 E5 D5 . C5 . B4 . . .",
+        bass: "A2 . A3 . A2 . A3 . F2 . F3 . G2 . G3 . \
+               D2 . D3 . D2 . D3 . E2 . E3 . E2 . G#2 .",
+        drums: "x . . . x . x . x . . . x . x . \
+                x . . . x . x . x . . . x . x x",
+    },
+    Theme {
+        name: "Lachesis",
+        bpm: 132,
+        lead: "D5 . F5 . A5 . G5 F5 E5 . C5 . D5 . . . \
+               F5 . E5 D5 C5 . A4 . C5 . D5 . E5 . . .",
+        bass: "D2 . D3 . C2 . C3 . A#1 . A#2 . C2 . C3 . \
+               D2 . D3 . F2 . F3 . G2 . G3 . A2 . A3 .",
+        drums: "x . x . x . x x x . x . x . x x \
+                x . x . x . x x x . x . x x x x",
+    },
+    Theme {
+        name: "Atropos",
+        bpm: 144,
+        lead: "E5 F5 E5 . B4 . E5 . G5 F5 E5 . D5 . E5 . \
+               E5 F5 G5 . A5 . G5 F5 E5 . D5 . C5 . B4 .",
+        bass: "E2 E3 E2 E3 E2 E3 E2 E3 F2 F3 F2 F3 E2 E3 E2 E3 \
+               C2 C3 C2 C3 D2 D3 D2 D3 E2 E3 E2 E3 B1 B2 B1 B2",
+        drums: "x . x . x x x . x . x . x x x x \
+                x . x . x x x . x . x . x x x x",
+    },
+];
+
+impl Theme {
+    // A theme for every third of the levels, the last one to the end.
+    pub fn for_level(level: usize) -> usize {
+        let levels = Level::MAX.div_ceil(THEMES.len());
+        ((level.max(Level::MIN) - Level::MIN) / levels).min(THEMES.len() - 1)
+    }
+
+    // Faster with every level, and faster still when the heap is near the top.
+    pub fn tempo(&self, level: usize, danger: bool) -> u32 {
+        let bpm = self.bpm + 4 * (level.max(Level::MIN) - Level::MIN) as u32;
+        if danger {
+            bpm * 5 / 4
+        } else {
+            bpm
+        }
+    }
+
+    fn voices(&self) -> [Voice; 3] {
+        [
+            Voice::new(Wave::Square, 0.08, self.lead),
+            Voice::new(Wave::Triangle, 0.20, self.bass),
+            Voice::new(Wave::Noise, 0.06, self.drums),
+        ]
+    }
+}
+
+// Semitones away from A4 of a note like `C#4`, the drums are all the same.
+fn parse_step(token: &str) -> Option<i32> {
+    if token == "x" {
+        return Some(0);
+    }
+    let mut chars = token.chars();
+    let name = match chars.next()? {
+        'C' => -9,
+        'D' => -7,
+        'E' => -5,
+        'F' => -4,
+        'G' => -2,
+        'A' => 0,
+        'B' => 2,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (sharp, octave) = match rest.strip_prefix('#') {
+        Some(octave) => (1, octave),
+        None => (0, rest),
+    };
+    let octave: i32 = octave.parse().ok()?;
+
+    Some(name + sharp + (octave - 4) * 12)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Voice {
+    wave: Wave,
+    volume: f32,
+    steps: Vec<Option<i32>>,
+}
+
+impl Voice {
+    fn new(wave: Wave, volume: f32, pattern: &str) -> Self {
+        Self {
+            wave,
+            volume,
+            steps: pattern.split_whitespace().map(parse_step).collect(),
+        }
+    }
+
+    fn tone(&self, step: usize, millis: u64) -> Option<Tone> {
+        let semitones = self.steps[step % self.steps.len()]?;
+        Some(match self.wave {
+            // a short burst makes for a crisper drum
+            Wave::Noise => Tone::new(self.wave, 0.0, 0.0, millis.min(40), self.volume),
+            _ => Tone::new(
+                self.wave,
+                note(semitones),
+                note(semitones),
+                millis,
+                self.volume,
+            ),
+        })
+    }
+}
+
+// Plays the themes step by step, handing out as many samples as asked for.
+#[derive(Debug, Clone)]
+pub struct Music {
+    theme: usize,
+    voices: [Voice; 3],
+    bpm: u32,
+    step: usize,
+    // rendered, but not handed out yet
+    pending: Vec<f32>,
+}
+
+impl Default for Music {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Music {
+    pub fn new(theme: usize) -> Self {
+        let theme = theme.min(THEMES.len() - 1);
+        Self {
+            theme,
+            voices: THEMES[theme].voices(),
+            bpm: THEMES[theme].bpm,
+            step: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn theme(&self) -> usize {
+        self.theme
+    }
+
+    pub fn bpm(&self) -> u32 {
+        self.bpm
+    }
+
+    // Switches to the theme of the level, from its start, and sets the tempo.
+    pub fn set_level(&mut self, level: usize, danger: bool) {
+        let theme = Theme::for_level(level);
+        if theme != self.theme {
+            *self = Self::new(theme);
+        }
+        self.bpm = THEMES[theme].tempo(level, danger);
+    }
+
+    // Number of steps in a loop of the current theme.
+    pub fn steps(&self) -> usize {
+        self.voices[0].steps.len()
+    }
+
+    // Every step is an eighth note.
+    fn step_len(&self, sample_rate: u32) -> usize {
+        (sample_rate as u64 * 60 / (self.bpm as u64 * 2)) as usize
+    }
+
+    fn render_step(&mut self, sample_rate: u32) {
+        let len = self.step_len(sample_rate);
+        let millis = len as u64 * 1000 / sample_rate as u64;
+        let at = self.pending.len();
+        self.pending.resize(at + len, 0.0);
+        for voice in self.voices.iter() {
+            if let Some(tone) = voice.tone(self.step, millis) {
+                let mut samples = Vec::with_capacity(len);
+                tone.render(sample_rate, &mut samples);
+                samples.truncate(len);
+                mix(&mut self.pending, at, &samples);
+            }
+        }
+        self.step = (self.step + 1) % self.steps();
+    }
+
+    pub fn render(&mut self, len: usize, sample_rate: u32) -> Vec<f32> {
+        while self.pending.len() < len {
+            self.render_step(sample_rate);
+        }
+        self.pending.drain(..len).collect()
+    }
+
+    // The given number of loops of every theme in a row, at their base tempo.
+    pub fn render_all(loops: usize, sample_rate: u32) -> Vec<f32> {
+        let mut samples = Vec::new();
+        for theme in 0..THEMES.len() {
+            let mut music = Self::new(theme);
+            let len = music.step_len(sample_rate) * music.steps() * loops;
+            samples.append(&mut music.render(len, sample_rate));
+        }
+        samples
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::audio::SAMPLE_RATE;
+
+    #[test]
+    fn test_parse_step() {
+        assert_eq!(parse_step("A4"), Some(0));
+        assert_eq!(parse_step("C5"), Some(3));
+        assert_eq!(parse_step("G#2"), Some(-25));
+        assert_eq!(parse_step("x"), Some(0));
+        assert_eq!(parse_step("."), None);
+        assert_eq!(parse_step("H4"), None);
+    }
+
+    #[test]
+    fn test_themes() {
+        for theme in THEMES {
+            let voices = theme.voices();
+            let steps = voices[0].steps.len();
+            assert!(voices.iter().all(|voice| voice.steps.len() == steps));
+            // only rests may be missing a note
+            for pattern in [theme.lead, theme.bass] {
+                assert!(pattern
+                    .split_whitespace()
+                    .all(|token| token == "." || parse_step(token).is_some()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_for_level() {
+        assert_eq!(Theme::for_level(Level::MIN), 0);
+        assert_eq!(Theme::for_level(6), 1);
+        assert_eq!(Theme::for_level(Level::MAX), THEMES.len() - 1);
+        assert_eq!(Theme::for_level(100), THEMES.len() - 1);
+    }
+
+    #[test]
+    fn test_tempo() {
+        let mut music = Music::default();
+        music.set_level(1, false);
+        let bpm = music.bpm();
+        music.set_level(2, false);
+        assert!(music.bpm() > bpm);
+        let bpm = music.bpm();
+        music.set_level(2, true);
+        assert!(music.bpm() > bpm);
+
+        music.set_level(Level::MAX, false);
+        assert_eq!(music.theme(), THEMES.len() - 1);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut music = Music::default();
+        let samples = music.render(1000, SAMPLE_RATE);
+        assert_eq!(samples.len(), 1000);
+        assert!(samples.iter().any(|sample| *sample != 0.0));
+        assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+
+        // in chunks or all at once, the music is the same
+        let mut chunked = Music::default();
+        let mut samples = chunked.render(300, SAMPLE_RATE);
+        samples.append(&mut chunked.render(700, SAMPLE_RATE));
+        assert_eq!(samples, Music::default().render(1000, SAMPLE_RATE));
+    }
+
+    #[test]
+    fn test_render_all() {
+        let samples = Music::render_all(1, SAMPLE_RATE);
+        let len: usize = (0..THEMES.len())
+            .map(|theme| {
+                let music = Music::new(theme);
+                music.step_len(SAMPLE_RATE) * music.steps()
+            })
+            .sum();
+
+        // every theme once, as rendered when the themes were written
+        assert_eq!(samples.len(), len);
+        assert_eq!(samples.len(), 967_488);
+        let energy: f64 = samples.iter().map(|s| f64::from(*s).powi(2)).sum();
+        assert!((energy - 4128.419).abs() < 0.01, "energy {energy}");
+        for (i, sample) in [
+            (1000, 0.130_980_5),
+            (50_000, -0.024_757_018),
+            (len / 2, 0.0),
+            (len - 1000, -0.014_772_151),
+        ] {
+            assert!((samples[i] - sample).abs() < 1e-5, "sample {i}");
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use crate::{
+    frame::{draw_text, Drawable, Frame, Pixel},
+    pit::PitSize,
+    PIT_STARTING_X,
+};
+use crossterm::style::Color;
+
+// Hides the pit behind a few lines of centered text.
+pub struct Overlay {
+    lines: Vec<String>,
+    size: PitSize,
+}
+
+impl Overlay {
+    pub fn new(size: PitSize, lines: &[&str]) -> Self {
+        Self {
+            lines: lines.iter().map(|line| line.to_string()).collect(),
+            size,
+        }
+    }
+}
+
+impl Drawable for Overlay {
+    fn draw(&self, frame: &mut Frame) {
+        let PitSize { cols, rows } = self.size;
+        for col in frame.iter_mut().skip(PIT_STARTING_X).take(cols) {
+            col.fill(Pixel::default());
+        }
+        let top = rows.saturating_sub(self.lines.len()) / 2;
+        for (y, line) in self.lines.iter().enumerate().take(rows) {
+            let left = cols.saturating_sub(line.chars().count()) / 2;
+            let line: String = line.chars().take(cols).collect();
+            draw_text(frame, PIT_STARTING_X + left, top + y, &line, Color::White);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::frame::new_frame;
+
+    #[test]
+    fn test_draw() {
+        let size = PitSize::default();
+        let mut frame = new_frame(size);
+        frame[PIT_STARTING_X][0].grapheme = '▓';
+        Overlay::new(size, &["PAUSED"]).draw(&mut frame);
+
+        assert_eq!(frame[PIT_STARTING_X][0], Pixel::default());
+        let text: String = (0..size.cols)
+            .map(|x| frame[PIT_STARTING_X + x][size.rows / 2].grapheme)
+            .collect();
+        assert_eq!(text, "PAUSED");
+    }
+}
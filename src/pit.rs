@@ -3,13 +3,128 @@ use crate::{
     column::Column,
     frame::{Drawable, Frame},
     point,
+    save::{parse_list, Persist, Save},
     timer::Timer,
-    Point, NUM_COLS, NUM_ROWS, PIT_STARTING_X,
+    Point, NUM_COLS, NUM_ROWS, PIT_STARTING_X, SIDE_PANEL_WIDTH,
 };
 use std::time::Duration;
-use std::{cmp::min, slice::Iter};
+use std::{
+    cmp::{min, Reverse},
+    ops::{Index, IndexMut},
+    slice::Iter,
+    str::FromStr,
+};
+
+// Dimensions of the pit in blocks, picked when the game starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PitSize {
+    pub cols: usize,
+    pub rows: usize,
+}
 
-pub type Heap = [[Block; NUM_ROWS]; NUM_COLS];
+impl Default for PitSize {
+    fn default() -> Self {
+        Self {
+            cols: NUM_COLS,
+            rows: NUM_ROWS,
+        }
+    }
+}
+
+impl PitSize {
+    // Three in a row must fit in any direction, and the board needs the height.
+    pub const MIN: Self = Self {
+        cols: 3,
+        rows: NUM_ROWS,
+    };
+    pub const MAX: Self = Self { cols: 16, rows: 32 };
+
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            cols: cols.clamp(Self::MIN.cols, Self::MAX.cols),
+            rows: rows.clamp(Self::MIN.rows, Self::MAX.rows),
+        }
+    }
+
+    // The frame holds the board, the pit and the side panel, from left to right.
+    pub fn frame_width(&self) -> usize {
+        PIT_STARTING_X + self.cols + SIDE_PANEL_WIDTH
+    }
+
+    pub fn frame_height(&self) -> usize {
+        self.rows
+    }
+
+    pub fn side_panel_x(&self) -> usize {
+        PIT_STARTING_X + self.cols + 2
+    }
+}
+
+// Parses sizes written as `<cols>x<rows>`, e.g. `8x16`.
+impl FromStr for PitSize {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (cols, rows) = s.split_once('x').ok_or(())?;
+        let cols = cols.parse().or(Err(()))?;
+        let rows = rows.parse().or(Err(()))?;
+        let size = Self::new(cols, rows);
+
+        if size == (Self { cols, rows }) {
+            Ok(size)
+        } else {
+            Err(())
+        }
+    }
+}
+
+// Blocks of the pit, indexed by column first: `heap[x][y]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heap {
+    cols: Vec<Vec<Block>>,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self::new(NUM_COLS, NUM_ROWS, None)
+    }
+}
+
+impl Heap {
+    pub fn new(cols: usize, rows: usize, block_kind: Option<BlockKind>) -> Self {
+        Self {
+            cols: vec![vec![Block::new(block_kind); rows]; cols],
+        }
+    }
+
+    #[inline]
+    pub fn cols(&self) -> usize {
+        self.cols.len()
+    }
+
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.cols.first().map_or(0, Vec::len)
+    }
+
+    pub fn iter(&self) -> Iter<'_, Vec<Block>> {
+        self.cols.iter()
+    }
+}
+
+impl Index<usize> for Heap {
+    type Output = Vec<Block>;
+
+    fn index(&self, x: usize) -> &Self::Output {
+        &self.cols[x]
+    }
+}
+
+impl IndexMut<usize> for Heap {
+    fn index_mut(&mut self, x: usize) -> &mut Self::Output {
+        &mut self.cols[x]
+    }
+}
 
 #[derive(Debug)]
 pub enum CardinalAxis {
@@ -35,10 +150,29 @@ enum PitStage {
     Dropping,
 }
 
+impl PitStage {
+    const ALL: [Self; 4] = [
+        Self::Stable,
+        Self::Matching,
+        Self::Collecting,
+        Self::Dropping,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Matching => "matching",
+            Self::Collecting => "collecting",
+            Self::Dropping => "dropping",
+        }
+    }
+}
+
 pub struct PitState {
     stage: PitStage,
     move_timer: Timer,
     times: u8,
+    chain: usize,
 }
 
 impl Default for PitState {
@@ -47,6 +181,7 @@ impl Default for PitState {
             stage: PitStage::Stable,
             move_timer: Timer::from_millis(Self::MOVE_MILLIS),
             times: 0,
+            chain: 0,
         }
     }
 }
@@ -55,15 +190,12 @@ impl PitState {
     const MOVE_MILLIS: u64 = 1000;
     pub const SCORE_MUL: usize = 10;
 
-    pub fn update_dropping_at<const R: usize, const C: usize>(
-        &self,
-        heap: &mut [[Block; R]; C],
-        origins: &mut [Point],
-    ) -> bool {
+    pub fn update_dropping_at(&self, heap: &mut Heap, origins: &mut [Point]) -> bool {
         let mut something_dropped = false;
+        let rows = heap.rows();
         // drop all active blocks one step if they have a slot for that
         for origin in origins.iter_mut() {
-            if origin.y < R - 1 && heap[origin.x][origin.y + 1].empty() {
+            if origin.y < rows - 1 && heap[origin.x][origin.y + 1].empty() {
                 // the slot below is empty, let's drop it we can drop one level!
                 // and let's update things accordingly in the heap
                 let new_item = heap[origin.x][origin.y].to_owned();
@@ -77,11 +209,7 @@ impl PitState {
         something_dropped
     }
 
-    pub fn collect_dropping_at<const R: usize, const C: usize>(
-        &self,
-        heap: &[[Block; R]; C],
-        origins: &[Point],
-    ) -> Vec<Point> {
+    pub fn collect_dropping_at(&self, heap: &Heap, origins: &[Point]) -> Vec<Point> {
         let mut items = Vec::new();
 
         for origin in origins {
@@ -94,19 +222,19 @@ impl PitState {
         }
 
         // sort by highest 'y' points first, so we don't run into troubles when updating next...
-        items.sort_unstable_by(|a, b| b.y.cmp(&a.y));
+        items.sort_unstable_by_key(|item| Reverse(item.y));
 
         items
     }
 
-    pub fn collect_matching_at<const R: usize, const C: usize>(
+    pub fn collect_matching_at(
         &self,
-        heap: &[[Block; R]; C],
+        heap: &Heap,
         origins: &[Point],
         partial_score: &mut usize,
     ) -> Vec<Point> {
         let mut items = Vec::new();
-        let mut cache = [[false; R]; C];
+        let mut cache = vec![vec![false; heap.rows()]; heap.cols()];
 
         for origin in origins {
             let (matches, number_axes) = self.matching_at(heap, origin);
@@ -123,13 +251,49 @@ impl PitState {
         items
     }
 
-    fn matching_at<const R: usize, const C: usize>(
+    // The magic jewel destroys every jewel of the color it landed on, along with itself.
+    pub fn collect_magic_at(
         &self,
-        heap: &[[Block; R]; C],
-        origin: &Point,
-    ) -> (Vec<Point>, usize) {
+        heap: &Heap,
+        origins: &[Point],
+        partial_score: &mut usize,
+    ) -> Vec<Point> {
+        let mut items = origins.to_vec();
+        let rows = heap.rows();
+        // origins start at the base of the column, the landing block lies right below it
+        let target = origins
+            .first()
+            .filter(|base| base.y < rows - 1)
+            .map(|base| heap[base.x][base.y + 1])
+            .filter(|block| !block.empty());
+
+        if let Some(target) = target {
+            for (x, cols) in heap.iter().enumerate() {
+                for (y, block) in cols.iter().enumerate() {
+                    if *block == target {
+                        items.push(point!(x, y));
+                        *partial_score += Self::SCORE_MUL;
+                    }
+                }
+            }
+        }
+
+        items
+    }
+
+    // The most axes lined up through any one of the origins.
+    fn axes_at(&self, heap: &Heap, origins: &[Point]) -> usize {
+        origins
+            .iter()
+            .map(|origin| self.matching_at(heap, origin).1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn matching_at(&self, heap: &Heap, origin: &Point) -> (Vec<Point>, usize) {
         let mut items = Vec::new();
         let mut matched_axes = 0;
+        let (cols, rows) = (heap.cols(), heap.rows());
         let origin_item = heap[origin.x][origin.y];
 
         if !origin_item.empty() {
@@ -146,7 +310,7 @@ impl PitState {
                             matches.push(point!(origin.x, y));
                         }
                         // south (S)
-                        for y in (origin.y + 1)..R {
+                        for y in (origin.y + 1)..rows {
                             if heap[origin.x][y] != origin_item {
                                 break;
                             }
@@ -162,8 +326,7 @@ impl PitState {
                             matches.push(point!(x, origin.y));
                         }
                         // east (E)
-                        #[allow(clippy::needless_range_loop)]
-                        for x in (origin.x + 1)..C {
+                        for x in (origin.x + 1)..cols {
                             if heap[x][origin.y] != origin_item {
                                 break;
                             }
@@ -172,14 +335,14 @@ impl PitState {
                     }
                     CardinalAxis::NExSW => {
                         // northeast (NE)
-                        for i in 1..min(C - origin.x, origin.y + 1) {
+                        for i in 1..min(cols - origin.x, origin.y + 1) {
                             if heap[origin.x + i][origin.y - i] != origin_item {
                                 break;
                             }
                             matches.push(point!(origin.x + i, origin.y - i));
                         }
                         // southwest (SW)
-                        for i in 1..min(R - origin.y, origin.x + 1) {
+                        for i in 1..min(rows - origin.y, origin.x + 1) {
                             if heap[origin.x - i][origin.y + i] != origin_item {
                                 break;
                             }
@@ -195,7 +358,7 @@ impl PitState {
                             matches.push(point!(origin.x - i, origin.y - i));
                         }
                         // southeast (SE)
-                        for i in 1..min(C - origin.x, R - origin.y) {
+                        for i in 1..min(cols - origin.x, rows - origin.y) {
                             if heap[origin.x + i][origin.y + i] != origin_item {
                                 break;
                             }
@@ -218,62 +381,144 @@ impl PitState {
     }
 }
 
+// What happened in the pit during an update, for anyone to show, play or
+// count, rather than telling it from the scores.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PitEvent {
+    // the column came to rest, its blocks at these positions
+    ColumnLanded {
+        positions: Vec<Point>,
+    },
+    // jewels to be cleared, lined up on as many axes at most, or struck by
+    // the magic jewel on none, worth these points
+    Matched {
+        cells: Vec<Point>,
+        axes: usize,
+        points: usize,
+    },
+    // the matched jewels are gone, those above are about to drop
+    Cleared,
+    // a match at this depth of the chain, 1 for the first one of a landing
+    CascadeStep {
+        depth: usize,
+    },
+    // nothing left to match or drop, the next column may come in
+    Settled,
+    // the heap settled reaching the top of the pit
+    ToppedOut,
+}
+
 pub struct Pit {
     pub heap: Heap,
     state: PitState,
     active_origins: Vec<Point>,
     score: usize,
     blocks_score: usize,
+    max_chain: usize,
 }
 
 impl Default for Pit {
     fn default() -> Self {
+        Self::new(PitSize::default())
+    }
+}
+
+impl Pit {
+    pub const DANGER_ROWS: usize = 4;
+
+    pub fn new(size: PitSize) -> Self {
         Self {
-            heap: Self::new_heap(None),
+            heap: Heap::new(size.cols, size.rows, None),
             active_origins: Vec::new(),
             state: PitState::default(),
             score: 0,
             blocks_score: 0,
+            max_chain: 0,
         }
     }
-}
 
-impl Pit {
-    pub fn new_heap<const R: usize, const C: usize>(
-        block_kind: Option<BlockKind>,
-    ) -> [[Block; R]; C] {
-        [[Block::new(block_kind); R]; C]
+    pub fn size(&self) -> PitSize {
+        PitSize {
+            cols: self.heap.cols(),
+            rows: self.heap.rows(),
+        }
     }
 
-    pub fn update(&mut self, column: &mut Column, delta: Duration) -> (usize, usize) {
+    pub fn update(&mut self, column: &mut Column, delta: Duration) -> Vec<PitEvent> {
         use PitStage::*;
 
+        let mut events = Vec::new();
+
         match &self.state.stage {
             Stable => {
                 if let Some(origins) = column.detect_landing(&mut self.heap, delta) {
-                    self.active_origins = origins;
-                    self.state.stage = Matching;
+                    events.push(PitEvent::ColumnLanded {
+                        positions: origins.clone(),
+                    });
+                    self.state.chain = 0;
                     self.state.move_timer.finish();
+
+                    let magic = origins
+                        .first()
+                        .is_some_and(|base| self.heap[base.x][base.y].magic());
+
+                    if magic {
+                        // the magic jewel skips matching altogether
+                        let mut partial_score = 0;
+                        let items =
+                            self.state
+                                .collect_magic_at(&self.heap, &origins, &mut partial_score);
+                        self.state.chain = 1;
+                        self.max_chain = self.max_chain.max(self.state.chain);
+                        self.score += partial_score;
+                        self.blocks_score += items.len() - origins.len();
+                        events.push(PitEvent::CascadeStep { depth: 1 });
+                        events.push(PitEvent::Matched {
+                            cells: items.clone(),
+                            axes: 0,
+                            points: partial_score,
+                        });
+                        self.active_origins = items;
+                        self.state.stage = Collecting;
+                    } else {
+                        self.active_origins = origins;
+                        self.state.stage = Matching;
+                    }
                 }
             }
             Matching => {
                 let mut partial_score = 0;
+                let axes = self.state.axes_at(&self.heap, &self.active_origins);
                 let items = self.state.collect_matching_at(
                     &self.heap,
                     &self.active_origins,
                     &mut partial_score,
                 );
+                // every further match within the same landing deepens the chain,
+                // and the deeper the chain, the more each match is worth
+                if !items.is_empty() {
+                    self.state.chain += 1;
+                    self.max_chain = self.max_chain.max(self.state.chain);
+                    events.push(PitEvent::CascadeStep {
+                        depth: self.state.chain,
+                    });
+                    events.push(PitEvent::Matched {
+                        cells: items.clone(),
+                        axes,
+                        points: partial_score * self.state.chain,
+                    });
+                }
                 // scoring
-                self.score += partial_score;
+                self.score += partial_score * self.state.chain;
                 self.blocks_score += items.len();
 
                 self.active_origins = items;
 
-                self.state.stage = if self.active_origins.is_empty() {
-                    Stable
+                if self.active_origins.is_empty() {
+                    self.settle(&mut events);
                 } else {
-                    Collecting
-                };
+                    self.state.stage = Collecting;
+                }
             }
             Collecting => {
                 if self.state.times == 3 {
@@ -284,21 +529,23 @@ impl Pit {
                         self.heap[item.x][item.y].update(None);
                     }
 
+                    events.push(PitEvent::Cleared);
+
                     self.active_origins = self
                         .state
                         .collect_dropping_at(&self.heap, &self.active_origins);
 
-                    self.state.stage = if self.active_origins.is_empty() {
-                        Stable
+                    if self.active_origins.is_empty() {
+                        self.settle(&mut events);
                     } else {
-                        Dropping
-                    };
+                        self.state.stage = Dropping;
+                    }
                 } else if self.state.move_timer.update(delta).ready() {
                     self.state.move_timer.reset();
                     self.state.times += 1;
 
                     if !self.active_origins.is_empty() {
-                        let exploding = self.state.times % 2 != 0;
+                        let exploding = !self.state.times.is_multiple_of(2);
 
                         for item in self.active_origins.iter() {
                             self.heap[item.x][item.y].exploding = exploding;
@@ -320,7 +567,30 @@ impl Pit {
             }
         }
 
-        (self.score, self.blocks_score)
+        events
+    }
+
+    // Back to waiting for the next column, unless there is no room left for it.
+    fn settle(&mut self, events: &mut Vec<PitEvent>) {
+        self.state.stage = PitStage::Stable;
+        events.push(PitEvent::Settled);
+        if self.topped_up() {
+            events.push(PitEvent::ToppedOut);
+        }
+    }
+
+    // Points earned outside of matching, e.g. by dropping columns faster.
+    pub fn reward(&mut self, points: usize) {
+        self.score += points;
+    }
+
+    // Whether the heap is about to reach the top of the pit.
+    pub fn danger(&self) -> bool {
+        self.heap.iter().any(|col| {
+            col.iter()
+                .take(Self::DANGER_ROWS)
+                .any(|block| !block.empty())
+        })
     }
 
     pub fn topped_up(&self) -> bool {
@@ -330,6 +600,82 @@ impl Pit {
     pub fn stable(&self) -> bool {
         self.state.stage == PitStage::Stable
     }
+
+    // Depth of the chain of matches caused by the last landing, 0 when nothing matched.
+    pub fn chain(&self) -> usize {
+        self.state.chain
+    }
+
+    // Deepest chain of the whole game.
+    pub fn max_chain(&self) -> usize {
+        self.max_chain
+    }
+
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    pub fn blocks_score(&self) -> usize {
+        self.blocks_score
+    }
+}
+
+// Everything down to the blinking of the jewels being cleared, so that a
+// game saved halfway through a chain goes on with it.
+impl Persist for Pit {
+    fn persist(&self, save: &mut Save) {
+        for y in 0..self.heap.rows() {
+            let row: Vec<Block> = self.heap.iter().map(|col| col[y]).collect();
+            save.set_list("pit.row", &row);
+        }
+        save.set("pit.stage", self.state.stage.name());
+        save.set("pit.move_timer", self.state.move_timer);
+        save.set("pit.times", self.state.times);
+        save.set("pit.chain", self.state.chain);
+        save.set_list("pit.origins", &self.active_origins);
+        save.set("pit.score", self.score);
+        save.set("pit.blocks_score", self.blocks_score);
+        save.set("pit.max_chain", self.max_chain);
+    }
+
+    fn restore(save: &Save, size: PitSize) -> Option<Self> {
+        let mut heap = Heap::new(size.cols, size.rows, None);
+        let mut rows = 0;
+        for (y, row) in save.all("pit.row").enumerate() {
+            let row: Vec<Block> = parse_list(row)?;
+            if y >= size.rows || row.len() != size.cols {
+                return None;
+            }
+            for (x, block) in row.into_iter().enumerate() {
+                heap[x][y] = block;
+            }
+            rows += 1;
+        }
+        let stage: String = save.get("pit.stage")?;
+        let stage = PitStage::ALL.into_iter().find(|s| s.name() == stage)?;
+        let active_origins: Vec<Point> = save.get_list("pit.origins")?;
+        if rows != size.rows
+            || active_origins
+                .iter()
+                .any(|origin| origin.x >= size.cols || origin.y >= size.rows)
+        {
+            return None;
+        }
+
+        Some(Self {
+            heap,
+            state: PitState {
+                stage,
+                move_timer: save.get("pit.move_timer")?,
+                times: save.get("pit.times")?,
+                chain: save.get("pit.chain")?,
+            },
+            active_origins,
+            score: save.get("pit.score")?,
+            blocks_score: save.get("pit.blocks_score")?,
+            max_chain: save.get("pit.max_chain")?,
+        })
+    }
 }
 
 impl Drawable for Pit {
@@ -347,11 +693,19 @@ mod test {
     use super::*;
     use crate::block::BlockKind;
 
-    type Heap = [[Block; 3]; 3];
-
     mod test_stage_transition {
         use super::*;
 
+        #[test]
+        fn test_danger() {
+            let mut pit = Pit::default();
+            pit.heap[2][Pit::DANGER_ROWS] = Block::new(Some(BlockKind::Red));
+            assert!(!pit.danger());
+            pit.heap[2][Pit::DANGER_ROWS - 1] = Block::new(Some(BlockKind::Red));
+            assert!(pit.danger());
+            assert!(!pit.topped_up());
+        }
+
         #[test]
         fn test_update_stable_stage() {
             let mut pit = Pit::default();
@@ -392,6 +746,215 @@ mod test {
         }
     }
 
+    mod test_pit_size {
+        use super::*;
+
+        #[test]
+        fn test_new() {
+            let pit = Pit::new(PitSize::new(8, 16));
+
+            assert_eq!(pit.heap.cols(), 8);
+            assert_eq!(pit.heap.rows(), 16);
+            assert_eq!(pit.size(), PitSize { cols: 8, rows: 16 });
+            assert_eq!(PitSize::new(1, 100), PitSize::new(3, 32));
+        }
+
+        #[test]
+        fn test_matching_in_wide_pit() {
+            // the far right corner of an 8x16 pit, out of reach of the default one
+            let mut pit = Pit::new(PitSize::new(8, 16));
+            pit.heap[5][15] = Block::new(Some(BlockKind::Red));
+            pit.heap[6][14] = Block::new(Some(BlockKind::Red));
+
+            let mut col = Column::from([
+                Block::new(Some(BlockKind::Red)),
+                Block::new(Some(BlockKind::Cyan)),
+                Block::new(Some(BlockKind::Red)),
+            ]);
+            col.enter(&pit.heap);
+            for _ in 0..10 {
+                col.move_right(&pit.heap);
+            }
+            col.hard_drop(&pit.heap);
+
+            for _ in 0..50 {
+                pit.update(&mut col, Duration::from_millis(Column::MOVE_MILLIS));
+            }
+            let scores = (pit.score(), pit.blocks_score());
+
+            // the top of the column completes a diagonal down to the far left red
+            assert_eq!(scores.1, 3);
+            assert!(pit.heap[5][15].empty());
+            assert!(pit.heap[6][14].empty());
+            assert!(!pit.heap[7][15].empty());
+        }
+    }
+
+    mod test_magic {
+        use super::*;
+
+        #[test]
+        fn test_collect_magic_at() {
+            // ┌─┬─┬─┐
+            // │ │◆│ │  ░ = Cyan
+            // ├─┼─┤─┤  ▒ = Red
+            // │▒│▒│░│  ◆ = Magic
+            // ├─┼─┼─┤
+            // │░│▒│▒│
+            // └─┴─┴─┘
+            let pit_state = PitState::default();
+            let mut heap = Heap::new(3, 3, Some(BlockKind::Red));
+            heap[0][2] = Block::new(Some(BlockKind::Cyan));
+            heap[2][1] = Block::new(Some(BlockKind::Cyan));
+            heap[0][0] = Block::default();
+            heap[2][0] = Block::default();
+            heap[1][0] = Block::new(Some(BlockKind::Magic));
+
+            let mut partial_score = 0;
+            let items = pit_state.collect_magic_at(&heap, &[point!(1, 0)], &mut partial_score);
+
+            assert_eq!(items.len(), 5);
+            assert_eq!(partial_score, 4 * PitState::SCORE_MUL);
+            for item in [
+                point!(1, 0),
+                point!(0, 1),
+                point!(1, 1),
+                point!(1, 2),
+                point!(2, 2),
+            ] {
+                assert!(items.contains(&item));
+            }
+        }
+
+        #[test]
+        fn test_collect_magic_at_bottom() {
+            let pit_state = PitState::default();
+            let mut heap = Heap::new(3, 3, None);
+            heap[0][2] = Block::new(Some(BlockKind::Magic));
+            heap[1][2] = Block::new(Some(BlockKind::Red));
+
+            let mut partial_score = 0;
+            let items = pit_state.collect_magic_at(&heap, &[point!(0, 2)], &mut partial_score);
+
+            assert_eq!(items, vec![point!(0, 2)]);
+            assert_eq!(partial_score, 0);
+        }
+
+        #[test]
+        fn test_update_magic_landing() {
+            let mut pit = Pit::default();
+            let bottom = NUM_ROWS - 1;
+            pit.heap[0][bottom] = Block::new(Some(BlockKind::Red));
+            pit.heap[1][bottom] = Block::new(Some(BlockKind::Cyan));
+            pit.heap[2][bottom] = Block::new(Some(BlockKind::Red));
+            pit.heap[3][bottom] = Block::new(Some(BlockKind::Red));
+
+            let mut col = Column::from([Block::new(Some(BlockKind::Magic)); 3]);
+            for _ in 1..NUM_ROWS {
+                col.move_down(&pit.heap);
+            }
+
+            let mut events = Vec::new();
+            for _ in 0..50 {
+                events.extend(pit.update(&mut col, Duration::from_millis(Column::MOVE_MILLIS)));
+            }
+            let scores = (pit.score(), pit.blocks_score());
+
+            assert!(pit.stable());
+            assert_eq!(scores, (3 * PitState::SCORE_MUL, 3));
+            // the magic jewels go along with the reds
+            assert!(matches!(
+                &events[2],
+                PitEvent::Matched { cells, axes: 0, points: 30 } if cells.len() == 6
+            ));
+            assert!(!pit.heap[1][bottom].empty());
+            assert!(pit
+                .heap
+                .iter()
+                .enumerate()
+                .all(|(x, cols)| x == 1 || cols.iter().all(Block::empty)));
+            assert!(pit.heap[1][..bottom].iter().all(Block::empty));
+        }
+    }
+
+    mod test_chain {
+        use super::*;
+
+        #[test]
+        fn test_chain_multiplier() {
+            // ┌─┬─┬─┐
+            // │ │ │░│  ░ = Cyan
+            // ├─┼─┤─┤  ▒ = Red
+            // │ │ │░│
+            // ├─┼─┼─┤  the cyan column is cleared first, then the red
+            // │▒│▒│░│  block drops down and matches the red row: chain x2
+            // └─┴─┴─┘
+            let mut pit = Pit::default();
+            let bottom = NUM_ROWS - 1;
+            pit.heap[0][bottom] = Block::new(Some(BlockKind::Red));
+            pit.heap[1][bottom] = Block::new(Some(BlockKind::Red));
+            pit.heap[2][bottom] = Block::new(Some(BlockKind::Cyan));
+
+            let mut col = Column::from([
+                Block::new(Some(BlockKind::Red)),
+                Block::new(Some(BlockKind::Cyan)),
+                Block::new(Some(BlockKind::Cyan)),
+            ]);
+            for _ in 1..NUM_ROWS {
+                col.move_down(&pit.heap);
+            }
+
+            assert_eq!(pit.chain(), 0);
+
+            let mut events = Vec::new();
+            for _ in 0..50 {
+                events.extend(pit.update(&mut col, Duration::from_millis(Column::MOVE_MILLIS)));
+            }
+            let scores = (pit.score(), pit.blocks_score());
+
+            assert!(pit.stable());
+            assert_eq!(pit.chain(), 2);
+            assert_eq!(pit.max_chain(), 2);
+            // 3 cyan blocks on one axis, then 3 red blocks on one axis times 2
+            assert_eq!(
+                scores,
+                (3 * PitState::SCORE_MUL + 3 * PitState::SCORE_MUL * 2, 6)
+            );
+            assert!((0..3).all(|x| pit.heap[x][bottom].empty()));
+
+            let positions = vec![
+                point!(2, bottom - 1),
+                point!(2, bottom - 2),
+                point!(2, bottom - 3),
+            ];
+            assert_eq!(
+                events,
+                [
+                    PitEvent::ColumnLanded { positions },
+                    PitEvent::CascadeStep { depth: 1 },
+                    PitEvent::Matched {
+                        cells: vec![
+                            point!(2, bottom - 2),
+                            point!(2, bottom),
+                            point!(2, bottom - 1)
+                        ],
+                        axes: 1,
+                        points: 3 * PitState::SCORE_MUL,
+                    },
+                    PitEvent::Cleared,
+                    PitEvent::CascadeStep { depth: 2 },
+                    PitEvent::Matched {
+                        cells: vec![point!(1, bottom), point!(0, bottom), point!(2, bottom)],
+                        axes: 1,
+                        points: 3 * PitState::SCORE_MUL * 2,
+                    },
+                    PitEvent::Cleared,
+                    PitEvent::Settled,
+                ]
+            );
+        }
+    }
+
     mod test_collect_matching {
         use super::*;
 
@@ -407,7 +970,7 @@ mod test {
             // └─┴─┴─┘
             //
             let pit_state = PitState::default();
-            let mut heap: [[Block; 3]; 3] = Pit::new_heap(None);
+            let mut heap = Heap::new(3, 3, None);
             let origins = [point!(0, 0), point!(0, 1)];
             for origin in origins.iter() {
                 heap[origin.x][origin.y] = Block::new(Some(BlockKind::Cyan));
@@ -438,7 +1001,7 @@ mod test {
                 }
             };
             let pit_state = PitState::default();
-            let mut heap = Pit::new_heap(None);
+            let mut heap = Heap::new(3, 3, None);
             let origins = [point!(0, 0), point!(0, 1), point!(0, 2)];
             for origin in origins.iter() {
                 heap[origin.x][origin.y] = Block::new(Some(BlockKind::Cyan));
@@ -468,7 +1031,7 @@ mod test {
                 }
             };
             let pit_state = PitState::default();
-            let mut heap = Pit::new_heap(None);
+            let mut heap = Heap::new(3, 3, None);
             let origins = [point!(0, 1), point!(1, 1), point!(2, 1)];
             for origin in origins.iter() {
                 heap[origin.x][origin.y] = Block::new(Some(BlockKind::Cyan));
@@ -498,7 +1061,7 @@ mod test {
                 }
             };
             let pit_state = PitState::default();
-            let mut heap = Pit::new_heap(None);
+            let mut heap = Heap::new(3, 3, None);
             let origins = [point!(0, 0), point!(1, 1), point!(2, 2)];
             for origin in origins.iter() {
                 heap[origin.x][origin.y] = Block::new(Some(BlockKind::Cyan));
@@ -530,7 +1093,7 @@ mod test {
                 }
             };
             let pit_state = PitState::default();
-            let mut heap = Pit::new_heap(None);
+            let mut heap = Heap::new(3, 3, None);
             let origins = [point!(2, 0), point!(1, 1), point!(0, 2)];
             for origin in origins.iter() {
                 heap[origin.x][origin.y] = Block::new(Some(BlockKind::Orange));
@@ -565,7 +1128,7 @@ mod test {
             };
 
             let pit_state = PitState::default();
-            let heap = Pit::new_heap(Some(BlockKind::Cyan));
+            let heap = Heap::new(3, 3, Some(BlockKind::Cyan));
             let origins = [point!(0, 0), point!(2, 2)];
             let matches = [
                 // ┌─┬─┬─┐
@@ -615,7 +1178,7 @@ mod test {
         use super::*;
 
         fn create_and_populate_heap_for_dropping() -> (Heap, [Point; 5], [Point; 2]) {
-            let mut heap: [[Block; 3]; 3] = Pit::new_heap(None);
+            let mut heap = Heap::new(3, 3, None);
             let origins = [
                 point!(0, 0),
                 point!(0, 1),
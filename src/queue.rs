@@ -0,0 +1,129 @@
+use crate::{
+    column::Shaft,
+    frame::{draw_text, Drawable, Frame},
+    pit::PitSize,
+    save::{parse_list, Persist, Save},
+    source::{ColumnSource, SeededSource},
+};
+use crossterm::style::Color;
+use std::collections::VecDeque;
+
+// The upcoming shafts, buffered ahead of the active column. However many are
+// shown, every shaft comes out of the source in the very same order.
+#[derive(Debug, Clone)]
+pub struct Queue {
+    source: SeededSource,
+    shafts: VecDeque<Shaft>,
+    size: PitSize,
+}
+
+impl Queue {
+    pub const MIN_LEN: usize = 1;
+    pub const MAX_LEN: usize = 5;
+
+    pub fn new(mut source: SeededSource, len: usize, size: PitSize) -> Self {
+        let len = len.clamp(Self::MIN_LEN, Self::MAX_LEN);
+        let shafts = (0..len).map(|_| source.next_shaft()).collect();
+
+        Self {
+            source,
+            shafts,
+            size,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.source.seed()
+    }
+
+    pub fn peek(&self) -> impl Iterator<Item = &Shaft> {
+        self.shafts.iter()
+    }
+}
+
+impl ColumnSource for Queue {
+    fn next_shaft(&mut self) -> Shaft {
+        self.shafts.push_back(self.source.next_shaft());
+        self.shafts.pop_front().unwrap()
+    }
+}
+
+impl Persist for Queue {
+    fn persist(&self, save: &mut Save) {
+        self.source.persist(save);
+        for shaft in self.shafts.iter() {
+            save.set_list("queue.shaft", shaft);
+        }
+    }
+
+    fn restore(save: &Save, size: PitSize) -> Option<Self> {
+        let shafts = save
+            .all("queue.shaft")
+            .map(|shaft| parse_list(shaft)?.try_into().ok())
+            .collect::<Option<VecDeque<Shaft>>>()?;
+        if !(Self::MIN_LEN..=Self::MAX_LEN).contains(&shafts.len()) {
+            return None;
+        }
+
+        Some(Self {
+            source: SeededSource::restore(save, size)?,
+            shafts,
+            size,
+        })
+    }
+}
+
+impl Drawable for Queue {
+    fn draw(&self, frame: &mut Frame) {
+        let x = self.size.side_panel_x();
+        draw_text(frame, x, 1, "Next", Color::White);
+        // side by side, the next one first
+        for (i, shaft) in self.shafts.iter().enumerate() {
+            for (y, block) in shaft.iter().enumerate() {
+                frame[x + 2 * i][2 + y] = block.to_pixel();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_same_stream() {
+        let mut source = SeededSource::new(7);
+        let mut short = Queue::new(SeededSource::new(7), 1, PitSize::default());
+        let mut long = Queue::new(SeededSource::new(7), Queue::MAX_LEN, PitSize::default());
+
+        for _ in 0..20 {
+            let shaft = source.next_shaft();
+            assert_eq!(short.next_shaft(), shaft);
+            assert_eq!(long.next_shaft(), shaft);
+        }
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut queue = Queue::new(SeededSource::new(7), 3, PitSize::default());
+        let upcoming: Vec<Shaft> = queue.peek().cloned().collect();
+
+        assert_eq!(queue.peek().count(), 3);
+        assert_eq!(queue.next_shaft(), upcoming[0]);
+        assert_eq!(queue.peek().next(), Some(&upcoming[1]));
+        assert_eq!(queue.peek().count(), 3);
+
+        assert_eq!(
+            Queue::new(SeededSource::new(7), 0, PitSize::default())
+                .peek()
+                .count(),
+            1
+        );
+        assert_eq!(
+            Queue::new(SeededSource::new(7), 9, PitSize::default())
+                .peek()
+                .count(),
+            5
+        );
+    }
+}
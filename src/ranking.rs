@@ -0,0 +1,264 @@
+use crate::xdg;
+use std::{
+    cmp::Reverse,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const FILE_NAME: &str = "ranking.txt";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub initials: String,
+    pub score: usize,
+    pub jewels: usize,
+    pub level: usize,
+    pub date: String,
+}
+
+impl Entry {
+    fn parse(line: &str) -> Option<Self> {
+        let mut entry = Entry {
+            initials: String::new(),
+            score: 0,
+            jewels: 0,
+            level: 0,
+            date: String::new(),
+        };
+        let mut has_score = false;
+        // fields are `key=value` pairs in any order, unknown keys are skipped,
+        // so that files written by newer versions can still be read
+        for field in line.split_whitespace() {
+            let Some((key, value)) = field.split_once('=') else {
+                continue;
+            };
+            match key {
+                "initials" => entry.initials = value.to_string(),
+                "score" => {
+                    entry.score = value.parse().ok()?;
+                    has_score = true;
+                }
+                "jewels" => entry.jewels = value.parse().unwrap_or_default(),
+                "level" => entry.level = value.parse().unwrap_or_default(),
+                "date" => entry.date = value.to_string(),
+                _ => {}
+            }
+        }
+        has_score.then_some(entry)
+    }
+
+    fn serialize(&self) -> String {
+        format!(
+            "initials={} score={} jewels={} level={} date={}",
+            self.initials, self.score, self.jewels, self.level, self.date
+        )
+    }
+}
+
+// Top scores of all times, kept sorted from the best down.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ranking {
+    entries: Vec<Entry>,
+}
+
+impl Ranking {
+    pub const SIZE: usize = 10;
+    pub const VERSION: u32 = 1;
+    pub const MAX_INITIALS: usize = 3;
+
+    // Where the ranking lives.
+    pub fn path() -> Option<PathBuf> {
+        xdg::data_file(FILE_NAME)
+    }
+
+    // A missing or unreadable ranking is just an empty one.
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| Self::load_from(&path).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        match Self::path() {
+            Some(path) => self.save_to(&path),
+            None => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "no data directory for the ranking",
+            )),
+        }
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        xdg::write(path, self.serialize())
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+        // the version is only informative for now: every version to date
+        // is readable by skipping the fields it does not know about
+        match lines
+            .next()
+            .and_then(|line| line.trim().strip_prefix("version="))
+        {
+            Some(version) if version.parse::<u32>().is_ok() => {}
+            _ => return Self::default(),
+        }
+        let mut entries: Vec<Entry> = lines.filter_map(Entry::parse).collect();
+        entries.sort_by_key(|entry| Reverse(entry.score));
+        entries.truncate(Self::SIZE);
+
+        Self { entries }
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut contents = format!("version={}\n", Self::VERSION);
+        for entry in self.entries.iter() {
+            contents.push_str(&entry.serialize());
+            contents.push('\n');
+        }
+        contents
+    }
+
+    pub fn qualifies(&self, score: usize) -> bool {
+        score > 0
+            && (self.entries.len() < Self::SIZE
+                || self.entries.last().is_some_and(|last| score > last.score))
+    }
+
+    // Ranks the entry below any equal score, returns its position if it made it.
+    pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+        let position = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(position, entry);
+        self.entries.truncate(Self::SIZE);
+
+        Some(position)
+    }
+
+    pub fn best(&self) -> usize {
+        self.entries.first().map_or(0, |entry| entry.score)
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+}
+
+// Current date as `YYYY-MM-DD` (UTC).
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    date_from_days(secs / 86_400)
+}
+
+// Converts days since the unix epoch to a civil date, see
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn date_from_days(days: u64) -> String {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entry(initials: &str, score: usize) -> Entry {
+        Entry {
+            initials: initials.to_string(),
+            score,
+            jewels: score / 10,
+            level: 1,
+            date: "2023-03-13".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut ranking = Ranking::default();
+        assert!(!ranking.qualifies(0));
+        assert_eq!(ranking.insert(entry("AAA", 100)), Some(0));
+        assert_eq!(ranking.insert(entry("BBB", 300)), Some(0));
+        assert_eq!(ranking.insert(entry("CCC", 100)), Some(2));
+        assert_eq!(ranking.best(), 300);
+
+        for score in 1..=Ranking::SIZE {
+            ranking.insert(entry("DDD", 1000 + score));
+        }
+        assert_eq!(ranking.entries().len(), Ranking::SIZE);
+        assert!(!ranking.qualifies(300));
+        assert_eq!(ranking.insert(entry("EEE", 300)), None);
+        assert_eq!(ranking.insert(entry("FFF", 1005)), Some(6));
+        assert_eq!(ranking.best(), 1010);
+    }
+
+    #[test]
+    fn test_serialize_and_parse() {
+        let mut ranking = Ranking::default();
+        ranking.insert(entry("AAA", 100));
+        ranking.insert(entry("BBB", 300));
+
+        assert_eq!(Ranking::parse(&ranking.serialize()), ranking);
+    }
+
+    #[test]
+    fn test_parse_other_versions() {
+        // newer fields are ignored, missing ones are defaulted
+        let contents = "version=7
+            score=50 initials=OLD
+            initials=NEW score=70 jewels=7 level=2 date=2030-01-01 chain=4 mode=arcade
+            initials=BAD score=abc";
+        let ranking = Ranking::parse(contents);
+
+        assert_eq!(ranking.entries().len(), 2);
+        assert_eq!(ranking.entries()[0].initials, "NEW");
+        assert_eq!(ranking.entries()[0].jewels, 7);
+        assert_eq!(ranking.entries()[1].initials, "OLD");
+        assert_eq!(ranking.entries()[1].date, "");
+
+        assert_eq!(Ranking::parse("garbage"), Ranking::default());
+        assert_eq!(Ranking::parse(""), Ranking::default());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("rust_columns_test_{}", std::process::id()))
+            .join(FILE_NAME);
+        let mut ranking = Ranking::default();
+        ranking.insert(entry("AAA", 100));
+
+        ranking.save_to(&path).unwrap();
+        assert_eq!(Ranking::load_from(&path).unwrap(), ranking);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_date_from_days() {
+        assert_eq!(date_from_days(0), "1970-01-01");
+        assert_eq!(date_from_days(19_429), "2023-03-13");
+        assert_eq!(date_from_days(11_016), "2000-02-29");
+        assert_eq!(today().len(), 10);
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{frame::Frame, NUM_COLS, NUM_ROWS, PIT_STARTING_X, WIDTH};
+use crate::{
+    frame::{Frame, Pixel},
+    pit::PitSize,
+    PIT_STARTING_X,
+};
 use crossterm::{cursor, style, terminal, QueueableCommand};
 use std::io::{Stdout, Write};
 
@@ -8,32 +12,117 @@ pub enum RendererError {
     MinimumSize(usize, usize),
 }
 
-pub fn assert_screen_size() -> Result<(), RendererError> {
-    let result = terminal::size().or(Err(RendererError::Size));
+// How many terminal cells, across and down, each pixel of the frame takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scale {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Default for Scale {
+    fn default() -> Self {
+        Self { x: 1, y: 1 }
+    }
+}
+
+impl Scale {
+    // From the largest down, terminal cells are about twice as tall as wide.
+    pub const ALL: [Scale; 4] = [
+        Scale { x: 4, y: 2 },
+        Scale { x: 2, y: 2 },
+        Scale { x: 2, y: 1 },
+        Scale { x: 1, y: 1 },
+    ];
+
+    // The largest scale at which the whole frame fits in the terminal.
+    pub fn fit(size: &PitSize, cols: usize, rows: usize) -> Option<Self> {
+        Self::ALL.into_iter().find(|scale| {
+            size.frame_width() * scale.x <= cols && size.frame_height() * scale.y <= rows
+        })
+    }
 
-    if let Ok((cols, rows)) = result {
-        if cols < WIDTH as u16 || rows < NUM_ROWS as u16 {
-            return Err(RendererError::MinimumSize(WIDTH, NUM_ROWS));
+    // The cells of the `dy`-th terminal row a pixel is made of. Block glyphs
+    // are repeated all over. Text can't be repeated and stay readable, so once
+    // pixels are two cells wide it takes its fullwidth form, twice as wide,
+    // on the first row of the pixel.
+    fn cells(&self, pixel: &Pixel, dy: usize) -> String {
+        let grapheme = pixel.grapheme;
+        if !grapheme.is_ascii() {
+            return grapheme.to_string().repeat(self.x);
+        }
+        if dy > 0 {
+            return " ".repeat(self.x);
+        }
+        match fullwidth(grapheme) {
+            Some(wide) if self.x >= 2 => format!("{wide}{}", " ".repeat(self.x - 2)),
+            _ => format!("{grapheme}{}", " ".repeat(self.x - 1)),
         }
-    } else {
-        return Err(result.unwrap_err());
     }
+}
 
-    Ok(())
+// The form of a printable ASCII character that takes two terminal cells.
+fn fullwidth(c: char) -> Option<char> {
+    match c {
+        '!'..='~' => char::from_u32(c as u32 - '!' as u32 + '！' as u32),
+        _ => None,
+    }
+}
+
+// Where the frame goes on the terminal: as large as it fits, and centered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub scale: Scale,
+    x: usize,
+    y: usize,
+}
+
+impl Layout {
+    pub fn fit(size: &PitSize, cols: usize, rows: usize) -> Option<Self> {
+        let scale = Scale::fit(size, cols, rows)?;
+
+        Some(Self {
+            scale,
+            x: (cols - size.frame_width() * scale.x) / 2,
+            y: (rows - size.frame_height() * scale.y) / 2,
+        })
+    }
+
+    // Terminal cell of the top left corner of the pixel at `x`, `y`.
+    fn cell(&self, x: usize, y: usize) -> (u16, u16) {
+        (
+            (self.x + x * self.scale.x) as u16,
+            (self.y + y * self.scale.y) as u16,
+        )
+    }
 }
 
-pub fn init(stdout: &mut Stdout) -> crossterm::Result<()> {
+// What the render thread is asked to do.
+pub enum Message {
+    Draw(Frame),
+    Resize(u16, u16),
+}
+
+pub fn assert_screen_size(size: &PitSize) -> Result<Layout, RendererError> {
+    let (cols, rows) = terminal::size().or(Err(RendererError::Size))?;
+
+    Layout::fit(size, cols as usize, rows as usize).ok_or(RendererError::MinimumSize(
+        size.frame_width(),
+        size.frame_height(),
+    ))
+}
+
+pub fn init(stdout: &mut Stdout, size: &PitSize, layout: Layout) -> crossterm::Result<()> {
     stdout
         .queue(style::SetBackgroundColor(style::Color::AnsiValue(67)))?
         .queue(terminal::Clear(terminal::ClearType::All))?
         .queue(style::SetBackgroundColor(style::Color::Black))?;
 
-    for x in 0..NUM_COLS {
-        for y in 0..NUM_ROWS {
-            stdout
-                .queue(cursor::MoveTo((x + PIT_STARTING_X) as u16, y as u16))?
-                .queue(style::Print(' '))?;
-        }
+    let blank = " ".repeat(size.cols * layout.scale.x);
+    let (x, y) = layout.cell(PIT_STARTING_X, 0);
+    for dy in 0..size.rows * layout.scale.y {
+        stdout
+            .queue(cursor::MoveTo(x, y + dy as u16))?
+            .queue(style::Print(&blank))?;
     }
 
     stdout.flush()?;
@@ -41,17 +130,45 @@ pub fn init(stdout: &mut Stdout) -> crossterm::Result<()> {
     Ok(())
 }
 
-pub fn render(stdout: &mut Stdout, last_frame: &Frame, frame: &Frame) -> crossterm::Result<()> {
+// Wipes the screen and tells the player how large it should be.
+pub fn too_small(stdout: &mut Stdout, size: &PitSize) -> crossterm::Result<()> {
+    stdout
+        .queue(style::SetBackgroundColor(style::Color::AnsiValue(67)))?
+        .queue(terminal::Clear(terminal::ClearType::All))?
+        .queue(style::SetForegroundColor(style::Color::White))?
+        .queue(cursor::MoveTo(0, 0))?
+        .queue(style::Print(format!(
+            "Window too small, {}x{} needed",
+            size.frame_width(),
+            size.frame_height()
+        )))?;
+
+    stdout.flush()?;
+
+    Ok(())
+}
+
+pub fn render(
+    stdout: &mut Stdout,
+    last_frame: &Frame,
+    frame: &Frame,
+    layout: Layout,
+) -> crossterm::Result<()> {
+    let scale = layout.scale;
     for (x, col) in frame.iter().enumerate() {
         for (y, cell) in col.iter().enumerate() {
-            if last_frame[x][y] == frame[x][y] {
+            if last_frame[x][y] == *cell {
                 continue;
             }
             stdout
-                .queue(cursor::MoveTo(x as u16, y as u16))?
                 .queue(style::SetForegroundColor(cell.color))?
-                .queue(style::SetBackgroundColor(cell.background))?
-                .queue(style::Print(cell.grapheme))?;
+                .queue(style::SetBackgroundColor(cell.background))?;
+            let (cell_x, cell_y) = layout.cell(x, y);
+            for dy in 0..scale.y {
+                stdout
+                    .queue(cursor::MoveTo(cell_x, cell_y + dy as u16))?
+                    .queue(style::Print(scale.cells(cell, dy)))?;
+            }
         }
     }
 
@@ -59,3 +176,79 @@ pub fn render(stdout: &mut Stdout, last_frame: &Frame, frame: &Frame) -> crosste
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fit() {
+        let size = PitSize::default();
+        let (width, height) = (size.frame_width(), size.frame_height());
+
+        assert_eq!(Scale::fit(&size, width - 1, height), None);
+        assert_eq!(Scale::fit(&size, width, height), Some(Scale::default()));
+        assert_eq!(
+            Scale::fit(&size, width * 3, height * 2),
+            Some(Scale { x: 2, y: 2 })
+        );
+        assert_eq!(
+            Scale::fit(&size, width * 4, height * 2),
+            Some(Scale { x: 4, y: 2 })
+        );
+    }
+
+    #[test]
+    fn test_layout() {
+        let size = PitSize::default();
+        let (width, height) = (size.frame_width(), size.frame_height());
+        let layout = Layout::fit(&size, width + 10, height + 5).unwrap();
+
+        assert_eq!(layout.scale, Scale::default());
+        assert_eq!(layout.cell(0, 0), (5, 2));
+        assert_eq!(layout.cell(1, 1), (6, 3));
+        assert_eq!(Layout::fit(&size, width, height - 1), None);
+    }
+
+    #[test]
+    fn test_cells() {
+        let scale = Scale { x: 2, y: 2 };
+        let block = Pixel {
+            grapheme: '▓',
+            ..Pixel::default()
+        };
+        let text = Pixel {
+            grapheme: 'S',
+            ..Pixel::default()
+        };
+
+        assert_eq!(scale.cells(&block, 1), "▓▓");
+        assert_eq!(scale.cells(&text, 0), "Ｓ");
+        assert_eq!(scale.cells(&text, 1), "  ");
+        assert_eq!(Scale::default().cells(&text, 0), "S");
+    }
+
+    #[test]
+    fn test_text_row() {
+        let row = |scale: Scale, dy: usize| -> String {
+            "Score 42"
+                .chars()
+                .map(|grapheme| {
+                    let pixel = Pixel {
+                        grapheme,
+                        ..Pixel::default()
+                    };
+                    scale.cells(&pixel, dy)
+                })
+                .collect()
+        };
+
+        assert_eq!(row(Scale { x: 2, y: 2 }, 0), "Ｓｃｏｒｅ  ４２");
+        assert_eq!(row(Scale { x: 2, y: 2 }, 1), " ".repeat(16));
+        assert_eq!(
+            row(Scale { x: 4, y: 2 }, 0),
+            "Ｓ  ｃ  ｏ  ｒ  ｅ      ４  ２  "
+        );
+        assert_eq!(row(Scale::default(), 0), "Score 42");
+    }
+}
@@ -0,0 +1,380 @@
+use crate::{
+    frame::{draw_text, Drawable, Frame},
+    game::{Game, GameEvent},
+    keymap::Action,
+    rules::Rules,
+    xdg,
+};
+use crossterm::style::Color;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const FILE_NAME: &str = "replay.txt";
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Version(u32),
+    Invalid(String),
+}
+
+impl From<io::Error> for ReplayError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+// A whole game, from the seed and the rules it was played with, and every
+// action of the player stamped with the tick of the game it was performed on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+    pub rules: Rules,
+    inputs: Vec<(u64, Action)>,
+    // the tick the player gave up on, unless the game went on to the end
+    end: Option<u64>,
+}
+
+impl Replay {
+    pub const VERSION: u32 = 1;
+
+    pub fn new(seed: u64, rules: Rules) -> Self {
+        Self {
+            seed,
+            rules,
+            inputs: Vec::new(),
+            end: None,
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, action: Action) {
+        self.inputs.push((tick, action));
+    }
+
+    pub fn finish(&mut self, tick: u64) {
+        self.end = Some(tick);
+    }
+
+    pub fn inputs(&self) -> &[(u64, Action)] {
+        &self.inputs
+    }
+
+    pub fn end(&self) -> Option<u64> {
+        self.end
+    }
+
+    // Where the last game is kept.
+    pub fn path() -> Option<PathBuf> {
+        xdg::data_file(FILE_NAME)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        xdg::write(path, self.serialize())
+    }
+
+    // Settings are `key=value` lines, inputs are `tick action` lines.
+    // Unknown settings are skipped, but a replay missing any input would
+    // play a different game, so broken input lines are an error.
+    pub fn parse(contents: &str) -> Result<Self, ReplayError> {
+        let mut seed = None;
+        let mut replay = Self::new(0, Rules::default());
+        for line in contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            let invalid = || ReplayError::Invalid(line.to_string());
+            if let Some((key, value)) = line.split_once('=') {
+                let rules = &mut replay.rules;
+                match key {
+                    "version" => {
+                        let version = value.parse().map_err(|_| invalid())?;
+                        if version > Self::VERSION {
+                            return Err(ReplayError::Version(version));
+                        }
+                    }
+                    "seed" => seed = Some(value.parse().map_err(|_| invalid())?),
+                    "end" => replay.end = Some(value.parse().map_err(|_| invalid())?),
+                    key => {
+                        rules.set_field(key, value).ok_or_else(invalid)?;
+                    }
+                }
+                continue;
+            }
+            let (tick, name) = line.split_once(' ').ok_or_else(invalid)?;
+            let tick = tick.parse().map_err(|_| invalid())?;
+            let action = name.trim().parse().map_err(|_| invalid())?;
+            replay.record(tick, action);
+        }
+        replay.seed = seed.ok_or(ReplayError::Invalid("no seed".to_string()))?;
+        Ok(replay)
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut contents = format!("version={}\nseed={}\n", Self::VERSION, self.seed);
+        for (key, value) in self.rules.fields() {
+            contents.push_str(&format!("{key}={value}\n"));
+        }
+        if let Some(end) = self.end {
+            contents.push_str(&format!("end={end}\n"));
+        }
+        for (tick, action) in self.inputs.iter() {
+            contents.push_str(&format!("{tick} {}\n", action.name()));
+        }
+        contents
+    }
+}
+
+// Hands out the inputs of a replay as its game clock goes by, at any speed.
+pub struct Playback {
+    replay: Replay,
+    next: usize,
+    speed: usize,
+    // game time due, but not played yet
+    lag: Duration,
+    pub paused: bool,
+}
+
+impl Playback {
+    // ratios rather than floats, to keep the game clock exact
+    pub const SPEEDS: [(u32, u32); 3] = [(1, 2), (1, 1), (4, 1)];
+
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next: 0,
+            speed: 1,
+            lag: Duration::ZERO,
+            paused: false,
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn speed(&self) -> f32 {
+        let (num, den) = Self::SPEEDS[self.speed];
+        num as f32 / den as f32
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(Self::SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    // How much game time a frame is worth.
+    pub fn scale(&self, delta: Duration) -> Duration {
+        if self.paused {
+            Duration::ZERO
+        } else {
+            let (num, den) = Self::SPEEDS[self.speed];
+            delta * num / den
+        }
+    }
+
+    // Plays the game on for as many ticks as the frame is worth at this
+    // speed, every input on the very tick it was recorded on.
+    pub fn step(&mut self, game: &mut Game, delta: Duration) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        // no more ticks than the fastest speed catches up with
+        let max_lag = Game::TICK * Game::MAX_CATCH_UP * Self::SPEEDS[Self::SPEEDS.len() - 1].0;
+        self.lag = (self.lag + self.scale(delta)).min(max_lag);
+        // not a tick past the end, where the player gave up
+        while self.lag >= Game::TICK && !game.over() && !self.finished(game.ticks()) {
+            self.lag -= Game::TICK;
+            let mut inputs = Vec::new();
+            while let Some((_, action)) = self.pop_due(game.ticks()) {
+                inputs.push(action);
+            }
+            events.extend(game.tick(&inputs));
+        }
+        events
+    }
+
+    // The next input, once the game clock has got to it.
+    pub fn pop_due(&mut self, tick: u64) -> Option<(u64, Action)> {
+        let input = *self.replay.inputs.get(self.next)?;
+        if input.0 > tick {
+            return None;
+        }
+        self.next += 1;
+        Some(input)
+    }
+
+    // Every input was played, and the player gave up by then.
+    pub fn finished(&self, tick: u64) -> bool {
+        self.next == self.replay.inputs.len() && self.replay.end.is_some_and(|end| tick >= end)
+    }
+}
+
+impl Drawable for Playback {
+    fn draw(&self, frame: &mut Frame) {
+        let size = self.replay.rules.pit;
+        let x = size.side_panel_x();
+        // at the bottom of the side panel, whatever the height of the pit
+        draw_text(frame, x, size.rows - 3, "Replay", Color::White);
+        let status = if self.paused {
+            "paused".to_string()
+        } else {
+            format!("{}x", self.speed())
+        };
+        draw_text(frame, x, size.rows - 2, &status, Color::White);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{mode::Mode, pit::PitSize};
+
+    #[test]
+    fn test_serialize_and_parse() {
+        let mut replay = Replay::new(
+            42,
+            Rules {
+                mode: Mode::Arcade,
+                level: 3,
+                ghost: false,
+                next: 4,
+                pit: PitSize::new(8, 16),
+                ..Rules::default()
+            },
+        );
+        replay.record(0, Action::Cycle);
+        replay.record(12, Action::MoveLeft);
+        replay.record(12, Action::Hold);
+        replay.finish(30);
+
+        assert_eq!(Replay::parse(&replay.serialize()).unwrap(), replay);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Replay::parse("version=1\n0 cycle"),
+            Err(ReplayError::Invalid(_))
+        ));
+        assert!(matches!(
+            Replay::parse("version=1\nseed=1\n3 fly"),
+            Err(ReplayError::Invalid(_))
+        ));
+        assert!(matches!(
+            Replay::parse("version=9\nseed=1"),
+            Err(ReplayError::Version(9))
+        ));
+        let replay = Replay::parse("seed=7\ncolor=blue\n\n5 hard_drop").unwrap();
+        assert_eq!(replay.rules, Rules::default());
+        assert_eq!(replay.inputs(), &[(5, Action::HardDrop)]);
+    }
+
+    #[test]
+    fn test_playback() {
+        let mut replay = Replay::new(1, Rules::default());
+        replay.record(10, Action::MoveLeft);
+        replay.record(20, Action::Cycle);
+        replay.finish(25);
+        let mut playback = Playback::new(replay);
+
+        assert_eq!(playback.pop_due(9), None);
+        assert_eq!(playback.pop_due(30), Some((10, Action::MoveLeft)));
+        assert_eq!(playback.pop_due(30), Some((20, Action::Cycle)));
+        assert_eq!(playback.pop_due(30), None);
+        assert!(!playback.finished(24));
+        assert!(playback.finished(25));
+
+        let delta = Duration::from_millis(100);
+        playback.faster();
+        playback.faster();
+        assert_eq!(playback.scale(delta), Duration::from_millis(400));
+        playback.paused = true;
+        assert_eq!(playback.scale(delta), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_draw() {
+        let size = PitSize::new(8, 16);
+        let rules = Rules {
+            pit: size,
+            ..Rules::default()
+        };
+        let mut frame = crate::frame::new_frame(size);
+        Playback::new(Replay::new(1, rules)).draw(&mut frame);
+
+        let x = size.side_panel_x();
+        let line =
+            |y: usize| -> String { frame[x..x + 6].iter().map(|col| col[y].grapheme).collect() };
+        assert_eq!(line(size.rows - 3), "Replay");
+        assert_eq!(line(size.rows - 2).trim_end(), "1x");
+    }
+
+    // Plays the replay back at every speed, up to the end of it.
+    fn play_back_at_every_speed(replay: &Replay) -> Vec<Game> {
+        let speeds: [fn(&mut Playback); 3] = [Playback::slower, |_| {}, Playback::faster];
+        speeds
+            .iter()
+            .map(|set_speed| {
+                let mut playback = Playback::new(replay.clone());
+                set_speed(&mut playback);
+                let mut game = Game::new(Some(replay.seed), replay.rules, 0);
+                while !playback.finished(game.ticks()) && !game.over() {
+                    playback.step(&mut game, Duration::from_millis(31));
+                }
+                game
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_play_back() {
+        let mut game = Game::new(Some(9), Rules::default(), 0);
+        for tick in 0..300 {
+            let inputs = match tick % 40 {
+                0 => vec![Action::MoveLeft, Action::Cycle],
+                20 => vec![Action::HardDrop],
+                _ => vec![],
+            };
+            game.tick(&inputs);
+        }
+        let replay = game.replay();
+
+        // at any speed, the very same game
+        for game_cmp in play_back_at_every_speed(&replay) {
+            assert_eq!(game_cmp.ticks(), game.ticks());
+            assert_eq!(game_cmp.replay(), replay);
+            assert_eq!(game_cmp.pit().score(), game.pit().score());
+        }
+    }
+
+    #[test]
+    fn test_play_back_to_the_end() {
+        // given up on long before the top out
+        let mut game = Game::new(Some(9), Rules::default(), 0);
+        for tick in 0..100 {
+            let inputs = match tick % 30 {
+                0 => vec![Action::MoveRight],
+                15 => vec![Action::Cycle],
+                _ => vec![],
+            };
+            game.tick(&inputs);
+        }
+        assert!(!game.over());
+        let replay = game.replay();
+        assert_eq!(replay.end(), Some(100));
+
+        for game_cmp in play_back_at_every_speed(&replay) {
+            assert_eq!(game_cmp.ticks(), 100);
+            assert_eq!(game_cmp.replay(), replay);
+        }
+    }
+}
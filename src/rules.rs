@@ -0,0 +1,107 @@
+use crate::{
+    level::Level,
+    mode::Mode,
+    pit::PitSize,
+    queue::Queue,
+    save::{Persist, Save},
+};
+
+// Settings that change how the game plays, chosen before it starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rules {
+    pub mode: Mode,
+    pub level: usize,
+    pub magic: bool,
+    // outline of where the column would land
+    pub ghost: bool,
+    // how many upcoming columns are shown
+    pub next: usize,
+    // a column may be set aside, once per landing
+    pub hold: bool,
+    pub pit: PitSize,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            mode: Mode::default(),
+            level: Level::MIN,
+            magic: true,
+            ghost: true,
+            next: Queue::MIN_LEN,
+            hold: true,
+            pit: PitSize::default(),
+        }
+    }
+}
+
+impl Rules {
+    // Every setting as a `key` and a `value`, as written in replays and saves.
+    pub fn fields(&self) -> [(&'static str, String); 7] {
+        [
+            ("mode", self.mode.name().to_string()),
+            ("level", self.level.to_string()),
+            ("magic", self.magic.to_string()),
+            ("ghost", self.ghost.to_string()),
+            ("next", self.next.to_string()),
+            ("hold", self.hold.to_string()),
+            ("pit", format!("{}x{}", self.pit.cols, self.pit.rows)),
+        ]
+    }
+
+    // Takes the value of a setting, returns whether the key is one of them,
+    // or nothing when the value is not one the setting takes.
+    pub fn set_field(&mut self, key: &str, value: &str) -> Option<bool> {
+        match key {
+            "mode" => self.mode = value.parse().ok()?,
+            "level" => self.level = value.parse().ok()?,
+            "magic" => self.magic = value.parse().ok()?,
+            "ghost" => self.ghost = value.parse().ok()?,
+            "next" => self.next = value.parse().ok()?,
+            "hold" => self.hold = value.parse().ok()?,
+            "pit" => self.pit = value.parse().ok()?,
+            _ => return Some(false),
+        }
+        Some(true)
+    }
+}
+
+impl Persist for Rules {
+    fn persist(&self, save: &mut Save) {
+        for (key, value) in self.fields() {
+            save.set(&format!("rules.{key}"), value);
+        }
+    }
+
+    fn restore(save: &Save, _size: PitSize) -> Option<Self> {
+        let mut rules = Self::default();
+        for (key, _) in Self::default().fields() {
+            let value = save.all(&format!("rules.{key}")).next()?;
+            rules.set_field(key, value)?;
+        }
+        Some(rules)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fields() {
+        let rules = Rules {
+            mode: Mode::Arcade,
+            hold: false,
+            pit: PitSize::new(8, 16),
+            ..Rules::default()
+        };
+        let mut parsed = Rules::default();
+        for (key, value) in rules.fields() {
+            assert_eq!(parsed.set_field(key, &value), Some(true));
+        }
+        assert_eq!(parsed, rules);
+
+        assert_eq!(parsed.set_field("color", "blue"), Some(false));
+        assert_eq!(parsed.set_field("hold", "maybe"), None);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{pit::PitSize, xdg};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+const FILE_NAME: &str = "save.txt";
+
+// A game put aside to be resumed later, as `key=value` lines. Every part of
+// the game writes and reads its own keys, a key may be repeated for lists.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Save {
+    fields: Vec<(String, String)>,
+}
+
+impl Save {
+    pub const VERSION: u32 = 1;
+
+    // Where the game is kept.
+    pub fn path() -> Option<PathBuf> {
+        xdg::data_file(FILE_NAME)
+    }
+
+    // Nothing to resume unless the file is there and readable by this version.
+    pub fn load(path: &Path) -> Option<Self> {
+        Self::parse(&fs::read_to_string(path).ok()?)
+    }
+
+    pub fn store(&self, path: &Path) -> io::Result<()> {
+        xdg::write(path, self.serialize())
+    }
+
+    // A game is resumed only once.
+    pub fn remove(path: &Path) -> io::Result<()> {
+        match fs::remove_file(path) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+
+    // Unlike other files, a save of a newer version is not read at all:
+    // resuming from a partial state would play a different game.
+    pub fn parse(contents: &str) -> Option<Self> {
+        let mut lines = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty());
+        let version: u32 = lines.next()?.strip_prefix("version=")?.parse().ok()?;
+        if version > Self::VERSION {
+            return None;
+        }
+        let fields = lines
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+
+        Some(Self { fields })
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut contents = format!("version={}\n", Self::VERSION);
+        for (key, value) in self.fields.iter() {
+            contents.push_str(&format!("{key}={value}\n"));
+        }
+        contents
+    }
+
+    pub fn set(&mut self, key: &str, value: impl ToString) {
+        self.fields.push((key.to_string(), value.to_string()));
+    }
+
+    // Writes the values on one line, comma separated.
+    pub fn set_list<T: ToString>(&mut self, key: &str, values: &[T]) {
+        let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+        self.set(key, values.join(","));
+    }
+
+    pub fn get<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.all(key).next()?.parse().ok()
+    }
+
+    pub fn get_list<T: FromStr>(&self, key: &str) -> Option<Vec<T>> {
+        parse_list(self.all(key).next()?)
+    }
+
+    // Every value of a repeated key, in order.
+    pub fn all<'a>(&'a self, key: &str) -> impl Iterator<Item = &'a str> {
+        let key = key.to_string();
+        self.fields
+            .iter()
+            .filter(move |(k, _)| *k == key)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Any part of a game that is put aside along with it, under keys of its own.
+// The size of the pit is known before anything else is restored.
+pub trait Persist: Sized {
+    fn persist(&self, save: &mut Save);
+    fn restore(save: &Save, size: PitSize) -> Option<Self>;
+}
+
+// Comma separated values, none at all when empty.
+pub fn parse_list<T: FromStr>(value: &str) -> Option<Vec<T>> {
+    if value.is_empty() {
+        return Some(Vec::new());
+    }
+    value.split(',').map(|item| item.parse().ok()).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_serialize_and_parse() {
+        let mut save = Save::default();
+        save.set("score", 120);
+        save.set_list("origins", &[1, 2, 3]);
+        save.set_list::<u8>("empty", &[]);
+        save.set("row", "a");
+        save.set("row", "b");
+
+        let save = Save::parse(&save.serialize()).unwrap();
+        assert_eq!(save.get::<usize>("score"), Some(120));
+        assert_eq!(save.get_list::<u8>("origins"), Some(vec![1, 2, 3]));
+        assert_eq!(save.get_list::<u8>("empty"), Some(vec![]));
+        assert_eq!(save.all("row").collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!(save.get::<usize>("row"), None);
+        assert_eq!(save.get::<usize>("level"), None);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert!(Save::parse("version=1\nscore=1").is_some());
+        assert!(Save::parse("version=2\nscore=1").is_none());
+        assert!(Save::parse("score=1").is_none());
+        assert!(Save::parse("").is_none());
+    }
+}
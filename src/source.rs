@@ -0,0 +1,183 @@
+use crate::{
+    block::{Block, BlockKind},
+    column::Shaft,
+    mode::Mode,
+    pit::PitSize,
+    save::{Persist, Save},
+};
+use rand::{distributions::Uniform, thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+// Anything able to hand out the shafts of the upcoming columns.
+pub trait ColumnSource {
+    fn next_shaft(&mut self) -> Shaft;
+}
+
+// Default generator: the same seed always yields the same sequence of shafts.
+#[derive(Debug, Clone)]
+pub struct SeededSource {
+    seed: u64,
+    rng: ChaCha8Rng,
+    colors: usize,
+    magic_probability: f64,
+}
+
+impl SeededSource {
+    pub const MAGIC_PROBABILITY: f64 = 1.0 / 64.0;
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            colors: Mode::default().colors(),
+            magic_probability: Self::MAGIC_PROBABILITY,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Number of jewel colors in play, the first ones of `BlockKind::JEWELS`.
+    pub fn set_colors(&mut self, colors: usize) {
+        self.colors = colors.clamp(Mode::Easy.colors(), BlockKind::JEWELS.len());
+    }
+
+    // Chance of any column being a magic jewel, from 0.0 (never) to 1.0 (always).
+    pub fn set_magic_probability(&mut self, probability: f64) {
+        self.magic_probability = probability.clamp(0.0, 1.0);
+    }
+}
+
+impl Default for SeededSource {
+    fn default() -> Self {
+        Self::new(thread_rng().gen())
+    }
+}
+
+impl ColumnSource for SeededSource {
+    fn next_shaft(&mut self) -> Shaft {
+        if self.rng.gen_bool(self.magic_probability) {
+            return [Block::new(Some(BlockKind::Magic)); 3];
+        }
+        let blocks = (&mut self.rng)
+            .sample_iter(Uniform::<usize>::new(0, self.colors))
+            .take(3)
+            .map(|index| Block::new(Some(BlockKind::JEWELS[index])))
+            .collect::<Vec<Block>>();
+
+        [blocks[0], blocks[1], blocks[2]]
+    }
+}
+
+// The seed and how far the generator went, rather than its whole state.
+impl Persist for SeededSource {
+    fn persist(&self, save: &mut Save) {
+        save.set("source.seed", self.seed);
+        save.set("source.word_pos", self.rng.get_word_pos());
+        save.set("source.colors", self.colors);
+        save.set("source.magic", self.magic_probability);
+    }
+
+    fn restore(save: &Save, _size: PitSize) -> Option<Self> {
+        let mut source = Self::new(save.get("source.seed")?);
+        source.rng.set_word_pos(save.get("source.word_pos")?);
+        source.set_colors(save.get("source.colors")?);
+        // clamped, but only once it's a number at all
+        let magic: f64 = save.get("source.magic")?;
+        if !magic.is_finite() {
+            return None;
+        }
+        source.set_magic_probability(magic);
+        Some(source)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut source = SeededSource::new(42);
+        let mut source_cmp = SeededSource::new(42);
+
+        for _ in 0..100 {
+            assert_eq!(source.next_shaft(), source_cmp.next_shaft());
+        }
+    }
+
+    #[test]
+    fn test_different_seed_different_sequence() {
+        let mut source = SeededSource::new(1);
+        let mut source_cmp = SeededSource::new(2);
+
+        let different = (0..10).any(|_| source.next_shaft() != source_cmp.next_shaft());
+
+        assert!(different);
+    }
+
+    #[test]
+    fn test_magic_probability() {
+        let mut source = SeededSource::new(3);
+        source.set_magic_probability(1.0);
+        assert!(source.next_shaft().iter().all(Block::magic));
+
+        source.set_magic_probability(0.0);
+        assert!((0..100).all(|_| !source.next_shaft().iter().any(Block::magic)));
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut source = SeededSource::new(7);
+        source.next_shaft();
+        let mut save = Save::default();
+        source.persist(&mut save);
+        let mut restored = SeededSource::restore(&save, PitSize::default()).unwrap();
+        assert_eq!(restored.next_shaft(), source.next_shaft());
+
+        for magic in ["NaN", "inf", "-inf"] {
+            let contents = save.serialize().replace(
+                &format!("source.magic={}", SeededSource::MAGIC_PROBABILITY),
+                &format!("source.magic={magic}"),
+            );
+            assert!(contents.contains(magic));
+            let save = Save::parse(&contents).unwrap();
+            assert!(SeededSource::restore(&save, PitSize::default()).is_none());
+        }
+    }
+
+    #[test]
+    fn test_colors() {
+        let kinds_for = |mode: Mode| {
+            let mut source = SeededSource::new(5);
+            source.set_magic_probability(0.0);
+            source.set_colors(mode.colors());
+
+            let mut kinds: Vec<BlockKind> = Vec::new();
+            for _ in 0..200 {
+                for block in source.next_shaft() {
+                    if !kinds.contains(&block.kind().unwrap()) {
+                        kinds.push(block.kind().unwrap());
+                    }
+                }
+            }
+            kinds
+        };
+
+        let kinds = kinds_for(Mode::Easy);
+        assert_eq!(kinds.len(), 4);
+        assert!(kinds
+            .iter()
+            .all(|kind| BlockKind::JEWELS[..4].contains(kind)));
+        assert_eq!(kinds_for(Mode::Arcade).len(), 6);
+    }
+
+    #[test]
+    fn test_default_seed() {
+        let source = SeededSource::default();
+        let mut source_cmp = SeededSource::new(source.seed());
+
+        assert_eq!(source.clone().next_shaft(), source_cmp.next_shaft());
+    }
+}
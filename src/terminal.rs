@@ -1,12 +1,21 @@
 use crossterm::{
     cursor::{Hide, Show},
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    event::{
+        DisableFocusChange, EnableFocusChange, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    terminal::{
+        disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
     ExecutableCommand,
 };
 use std::io;
 
 // Failure-guarded setup and cleanup for the terminal.
-pub struct TerminalGuard;
+pub struct TerminalGuard {
+    enhanced: bool,
+}
 
 impl TerminalGuard {
     pub fn create() -> TerminalGuard {
@@ -14,13 +23,34 @@ impl TerminalGuard {
         enable_raw_mode().unwrap();
         stdout.execute(EnterAlternateScreen).unwrap();
         stdout.execute(Hide).unwrap();
-        TerminalGuard
+        stdout.execute(EnableFocusChange).unwrap();
+        // key releases are only reported by terminals supporting the kitty protocol
+        let enhanced = supports_keyboard_enhancement().unwrap_or(false);
+        if enhanced {
+            stdout
+                .execute(PushKeyboardEnhancementFlags(
+                    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                        | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                        | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES,
+                ))
+                .unwrap();
+        }
+        TerminalGuard { enhanced }
+    }
+
+    // Whether key presses, repeats and releases are told apart.
+    pub fn enhanced(&self) -> bool {
+        self.enhanced
     }
 }
 
 impl Drop for TerminalGuard {
     fn drop(&mut self) {
         let mut stdout = io::stdout();
+        if self.enhanced {
+            stdout.execute(PopKeyboardEnhancementFlags).unwrap();
+        }
+        stdout.execute(DisableFocusChange).unwrap();
         stdout.execute(LeaveAlternateScreen).unwrap();
         stdout.execute(Show).unwrap();
         disable_raw_mode().unwrap();
@@ -1,6 +1,6 @@
-use std::time::Duration;
+use std::{fmt, str::FromStr, time::Duration};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Timer {
     ready: bool,
     millis: u64,
@@ -37,6 +37,35 @@ impl Timer {
     }
 }
 
+// Written in saved games as `millis:nanos left`, then `!` once ready.
+impl fmt::Display for Timer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.millis, self.duration.as_nanos())?;
+        if self.ready {
+            write!(f, "!")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Timer {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (s, ready) = match s.strip_suffix('!') {
+            Some(s) => (s, true),
+            None => (s, false),
+        };
+        let (millis, nanos) = s.split_once(':').ok_or(())?;
+
+        Ok(Self {
+            ready,
+            millis: millis.parse().or(Err(()))?,
+            duration: Duration::from_nanos(nanos.parse().or(Err(()))?),
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -54,4 +83,16 @@ mod test {
         timer.finish();
         assert!(timer.ready());
     }
+
+    #[test]
+    fn test_to_string_and_parse() {
+        let mut timer = Timer::from_millis(1000);
+        timer.update(Duration::from_nanos(1500));
+        assert_eq!(timer.to_string(), "1000:999998500");
+        assert_eq!(timer.to_string().parse(), Ok(timer));
+        timer.finish();
+        assert_eq!(timer.to_string(), "1000:0!");
+        assert_eq!(timer.to_string().parse(), Ok(timer));
+        assert_eq!("1000".parse::<Timer>(), Err(()));
+    }
 }
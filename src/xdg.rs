@@ -0,0 +1,66 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+// Files of the game, in a directory of its own, following the XDG base
+// directory specification.
+
+// Under `$XDG_DATA_HOME`, `~/.local/share` by default.
+pub fn data_file(name: &str) -> Option<PathBuf> {
+    file("XDG_DATA_HOME", ".local/share", name)
+}
+
+// Under `$XDG_CONFIG_HOME`, `~/.config` by default.
+pub fn config_file(name: &str) -> Option<PathBuf> {
+    file("XDG_CONFIG_HOME", ".config", name)
+}
+
+// Writes the file, along with the directory of the game the first time.
+pub fn write(path: &Path, contents: String) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, contents)
+}
+
+// An empty variable is the same as a missing one.
+fn file(var: &str, default: &str, name: &str) -> Option<PathBuf> {
+    let base = env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(default)))?;
+
+    Some(base.join(env!("CARGO_PKG_NAME")).join(name))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_file() {
+        let var = "RUST_COLUMNS_TEST_XDG_HOME";
+        env::set_var(var, "/tmp/xdg");
+        assert_eq!(
+            file(var, ".local/share", "ranking.txt"),
+            Some(PathBuf::from("/tmp/xdg/rust_columns/ranking.txt"))
+        );
+
+        env::set_var(var, "");
+        let home = env::var_os("HOME").map(PathBuf::from);
+        assert_eq!(
+            file(var, ".config", "keys.txt"),
+            home.map(|home| home.join(".config/rust_columns/keys.txt"))
+        );
+    }
+
+    #[test]
+    fn test_write() {
+        let dir = env::temp_dir().join(format!("rust_columns_xdg_{}", std::process::id()));
+        let path = dir.join("rust_columns").join("save.txt");
+        write(&path, "version=1\n".to_string()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "version=1\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}